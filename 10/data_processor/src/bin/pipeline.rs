@@ -0,0 +1,24 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use data_processor::cli::CliConfig;
+use std::env;
+use std::io;
+use std::process;
+
+fn main() {
+    let config = CliConfig::build(env::args()).unwrap_or_else(|err| {
+        eprintln!("Couldn't construct config: {err}");
+        eprintln!("{}", CliConfig::USAGE);
+        process::exit(2);
+    });
+
+    // 0 - every item made it, 1 - a stage failed on some items, 2 - the run failed
+    match config.run(io::stdin().lock(), io::stdout().lock(), io::stderr().lock()) {
+        Ok(0) => {}
+        Ok(_) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(2);
+        }
+    }
+}
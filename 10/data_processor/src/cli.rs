@@ -0,0 +1,182 @@
+use crate::data_pipeline::Report;
+use crate::errors::PipelineError;
+use crate::registry::{PipelineDescription, Registry, Value, ValueLines};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
+
+/// Command line configuration of the `pipeline` binary
+#[derive(Debug)]
+pub struct CliConfig {
+    description: PipelineDescription,
+    input: Option<String>,
+    threads: Option<NonZeroUsize>,
+}
+
+impl CliConfig {
+    pub const USAGE: &'static str = "Usage: pipeline [--threads N] DESCRIPTION [INPUT]
+Runs the pipeline described by the .toml or .json file DESCRIPTION over the lines of INPUT,
+or of stdin without it. The output is printed as JSON to stdout, the report to stderr.";
+
+    /// Builds the config from the program arguments, the first one is the program name
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - No description was given, or more than one input
+    /// - `--threads` is missing its value or it is not a positive number
+    /// - The description can't be read or parsed
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Self, PipelineError> {
+        args.next();
+
+        let mut threads = None;
+        let mut paths = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-t" | "--threads" => {
+                    let Some(count) = args.next().and_then(|count| count.parse().ok()) else {
+                        return Err(Self::invalid("--threads expects a positive number"));
+                    };
+                    threads = Some(count);
+                }
+                _ => paths.push(arg),
+            }
+        }
+
+        let mut paths = paths.into_iter();
+        let Some(description) = paths.next() else {
+            return Err(Self::invalid("Please provide a pipeline description"));
+        };
+        let input = paths.next();
+        if paths.next().is_some() {
+            return Err(Self::invalid("Only a single input can be given"));
+        }
+
+        Ok(Self {
+            description: PipelineDescription::from_file(description)?,
+            input,
+            threads,
+        })
+    }
+
+    /// Runs the pipeline over `stdin` unless an input file was given
+    ///
+    /// Returns how many items a stage failed on
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError::InvalidPipeline` if the description names unknown stages,
+    /// `PipelineError::Io` if the input can't be read or the output can't be written,
+    /// or the error that stopped the run
+    pub fn run<R, W, E>(&self, stdin: R, mut out: W, mut err: E) -> Result<usize, PipelineError>
+    where
+        R: BufRead,
+        W: Write,
+        E: Write,
+    {
+        let registry = Registry::new();
+        let processor = self.description.build(&registry)?;
+
+        let reader: Box<dyn BufRead + '_> = match &self.input {
+            Some(path) => Box::new(BufReader::new(File::open(path).map_err(|source| {
+                PipelineError::Io {
+                    context: format!("Could not open {path}"),
+                    source,
+                }
+            })?)),
+            None => Box::new(stdin),
+        };
+        let source = ValueLines::new(reader, self.description.input());
+
+        let report = match self.threads {
+            Some(threads) => processor.process_data_parallel(source, threads)?,
+            None => processor.process_data(source)?,
+        };
+
+        Self::print(&report, &mut out, &mut err).map_err(|source| PipelineError::Io {
+            context: "Could not write the output".to_string(),
+            source,
+        })?;
+        Ok(report.rejected())
+    }
+
+    fn print(
+        report: &Report<Value>,
+        out: &mut impl Write,
+        err: &mut impl Write,
+    ) -> std::io::Result<()> {
+        serde_json::to_writer(&mut *out, report.output())?;
+        writeln!(out)?;
+        writeln!(err, "{report}")
+    }
+
+    fn invalid(message: &str) -> PipelineError {
+        PipelineError::InvalidArguments(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_build_no_description_fail() {
+        let result = CliConfig::build(args(&["ignore", "--threads", "2"]));
+        assert!(matches!(result, Err(PipelineError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn test_build_zero_threads_fail() {
+        let result = CliConfig::build(args(&[
+            "ignore",
+            "--threads",
+            "0",
+            "pipelines/even_sum.toml",
+        ]));
+        assert!(matches!(result, Err(PipelineError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn test_run_stdin() -> Result<(), PipelineError> {
+        let config = CliConfig::build(args(&["ignore", "-t", "2", "pipelines/even_sum.toml"]))?;
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        let rejected = config.run("1\n2\nthree\n4\n".as_bytes(), &mut out, &mut err)?;
+
+        assert_eq!(rejected, 1);
+        assert_eq!(String::from_utf8_lossy(&out), "12\n");
+        assert!(
+            String::from_utf8_lossy(&err)
+                .starts_with("4 items read, 2 aggregated, 0 skipped, 1 dead letters")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_file() -> Result<(), PipelineError> {
+        let config = CliConfig::build(args(&[
+            "ignore",
+            "pipelines/shout.json",
+            "pipelines/words.txt",
+        ]))?;
+        let mut out = Vec::new();
+
+        let rejected = config.run("".as_bytes(), &mut out, Vec::new())?;
+
+        assert_eq!(rejected, 0);
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "[\"CRAB\",\"HERMIT CRAB\"]\n"
+        );
+        Ok(())
+    }
+}
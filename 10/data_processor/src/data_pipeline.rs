@@ -0,0 +1,546 @@
+use crate::datasource::Source;
+use crate::errors::PipelineError;
+use crate::pipeline::{Aggregator, Filter, Transformer};
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod builder;
+mod observer;
+mod report;
+
+pub use builder::Pipeline;
+pub use observer::{LoggingObserver, MetricsObserver, Observer, StageEvent};
+use report::Tally;
+pub use report::{DeadLetter, ErrorPolicy, Report, Stage};
+
+pub struct DataProcessor<T, F, U, V, A, P>
+where
+    T: Debug,
+    U: Debug,
+    F: Filter<T> + Debug,
+    P: Transformer<T, U> + Debug,
+    A: Aggregator<U, V> + Debug,
+{
+    filter: F,
+    transformer: P,
+    aggregator: A,
+    source_policy: ErrorPolicy,
+    transform_policy: ErrorPolicy,
+    observer: Option<Arc<dyn Observer>>,
+    // fn() keeps the processor Send + Sync no matter what T, U and V are
+    #[allow(clippy::type_complexity)]
+    _phantom: PhantomData<fn() -> (T, U, V)>,
+}
+
+impl<T, F, U, V, A, P> DataProcessor<T, F, U, V, A, P>
+where
+    T: Debug,
+    U: Debug,
+    F: Filter<T> + Debug,
+    P: Transformer<T, U> + Debug,
+    A: Aggregator<U, V> + Debug,
+{
+    // Items read from the source per worker thread before the workers start
+    const ITEMS_PER_THREAD: usize = 1024;
+
+    #[must_use]
+    pub fn new(filter: F, transformer: P, aggregator: A) -> Self {
+        Self {
+            filter,
+            transformer,
+            aggregator,
+            source_policy: ErrorPolicy::default(),
+            transform_policy: ErrorPolicy::default(),
+            observer: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// What to do with the items the source fails to produce, fail fast by default
+    #[must_use]
+    pub fn with_source_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.source_policy = policy;
+        self
+    }
+
+    /// What to do with the items the transformer fails on, fail fast by default
+    #[must_use]
+    pub fn with_transform_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.transform_policy = policy;
+        self
+    }
+
+    /// Reports every run to `observer`, keep a clone of the `Arc` to read it afterwards
+    #[must_use]
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn transform(&self, entry: &T) -> Result<U, PipelineError> {
+        self.transformer.transform(entry).ok_or_else(|| {
+            self.transformer.failure(entry).unwrap_or_else(|| {
+                PipelineError::TransformError(format!(
+                    "Could not transform {entry:?} using {:?}",
+                    self.transformer
+                ))
+            })
+        })
+    }
+
+    fn aggregate(&self, state: A::State, tally: &mut Tally) -> Result<V, PipelineError> {
+        let output = self.timed(tally.elapsed_mut(Stage::Aggregate), || {
+            self.aggregator
+                .check(&state)
+                .map(|()| self.aggregator.finish(state))
+        })?;
+        tally.finished = output.is_some();
+        output.ok_or_else(|| {
+            PipelineError::AggregatorError(format!(
+                "Couldn't aggregate {} items using {:?}",
+                tally.aggregated, self.aggregator
+            ))
+        })
+    }
+
+    // Measures how long f takes into elapsed, nobody looks at it without an observer
+    fn timed<R>(&self, elapsed: &mut Duration, f: impl FnOnce() -> R) -> R {
+        if self.observer.is_none() {
+            return f();
+        }
+        let start = Instant::now();
+        let result = f();
+        *elapsed += start.elapsed();
+        result
+    }
+
+    // Runs a single source entry through the stages, position is 1-based
+    fn process_entry<X: Borrow<T>>(
+        &self,
+        position: usize,
+        entry: Result<X, PipelineError>,
+        state: &mut A::State,
+        tally: &mut Tally,
+    ) -> Result<(), PipelineError> {
+        tally.read += 1;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return tally.reject(self.source_policy, Stage::Source, position, None, e),
+        };
+        tally.produced += 1;
+
+        let entry = entry.borrow();
+        if !self.timed(tally.elapsed_mut(Stage::Filter), || {
+            self.filter.filter(entry)
+        }) {
+            return Ok(());
+        }
+        tally.passed += 1;
+
+        match self.timed(tally.elapsed_mut(Stage::Transform), || {
+            self.transform(entry)
+        }) {
+            Ok(transformed_entry) => {
+                tally.aggregated += 1;
+                self.timed(tally.elapsed_mut(Stage::Aggregate), || {
+                    self.aggregator.accumulate(state, transformed_entry);
+                });
+                Ok(())
+            }
+            Err(e) => tally.reject(
+                self.transform_policy,
+                Stage::Transform,
+                position,
+                Some(entry),
+                e,
+            ),
+        }
+    }
+
+    // Tells the observer how the run went and hands back its result
+    fn finish_run<S>(
+        &self,
+        started: Instant,
+        tally: Tally,
+        output: Result<V, PipelineError>,
+    ) -> Result<Report<V>, PipelineError> {
+        if let Some(observer) = &self.observer {
+            let names = [
+                short_type_name::<S>().to_string(),
+                format!("{:?}", self.filter),
+                format!("{:?}", self.transformer),
+                format!("{:?}", self.aggregator),
+            ];
+            for (stage, (name, elapsed)) in Stage::ALL
+                .into_iter()
+                .zip(names.into_iter().zip(tally.elapsed))
+            {
+                observer.stage_finished(&StageEvent::new(
+                    stage,
+                    name,
+                    tally.items(stage),
+                    tally.rejected(stage),
+                    elapsed,
+                ));
+            }
+            observer.run_finished(started.elapsed(), output.as_ref().err());
+        }
+        Ok(Report::new(output?, tally))
+    }
+
+    /// Runs the filter, transformer and aggregator over the source, one item at a time
+    ///
+    /// Only the aggregator's state is kept in memory, so the source can be bigger than memory.
+    /// Items the source or the transformer fail on are handled by the stage's `ErrorPolicy`
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError` if the source fails or an item could not be transformed
+    /// with a `FailFast` policy, or if the result could not be aggregated
+    // 0 clone calls ;)
+    pub fn process_data<S>(&self, source: S) -> Result<Report<V>, PipelineError>
+    where
+        S: Source<Value = T>,
+    {
+        let started = self.start_run();
+        let mut state = self.aggregator.init();
+        let mut tally = Tally::default();
+        let mut items = source.into_items().enumerate();
+
+        let mut output = Ok(());
+        while let Some((index, entry)) =
+            self.timed(tally.elapsed_mut(Stage::Source), || items.next())
+        {
+            output = self.process_entry(index + 1, entry, &mut state, &mut tally);
+            if output.is_err() {
+                break;
+            }
+        }
+        let output = output.and_then(|()| self.aggregate(state, &mut tally));
+
+        self.finish_run::<S>(started, tally, output)
+    }
+
+    /// Same as `process_data`, but filters and transforms on `threads` worker threads
+    ///
+    /// The source is read in chunks, every worker gets a consecutive part of a chunk and
+    /// the partial results are combined in order with `Aggregator::merge`,
+    /// so the result is exactly the one `process_data` gives.
+    /// Runs with an aggregator that is not `Aggregator::mergeable` fall back to `process_data`
+    ///
+    /// # Errors
+    ///
+    /// Returns the same error `process_data` would return
+    pub fn process_data_parallel<S>(
+        &self,
+        source: S,
+        threads: NonZeroUsize,
+    ) -> Result<Report<V>, PipelineError>
+    where
+        S: Source<Value = T>,
+        S::Item: Send,
+        F: Sync,
+        P: Sync,
+        A: Sync,
+        A::State: Send,
+    {
+        if !self.aggregator.mergeable() {
+            return self.process_data(source);
+        }
+        let started = self.start_run();
+        let chunk_size = threads.get() * Self::ITEMS_PER_THREAD;
+        let mut items = source.into_items().enumerate();
+        let mut state = self.aggregator.init();
+        let mut tally = Tally::default();
+
+        let output = loop {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            while let Some((index, entry)) =
+                self.timed(tally.elapsed_mut(Stage::Source), || items.next())
+            {
+                // Nothing after a fatal source error may be read, the worker reports it in order
+                let fatal = entry.is_err() && self.source_policy == ErrorPolicy::FailFast;
+                chunk.push((index + 1, entry));
+                if fatal || chunk.len() == chunk_size {
+                    break;
+                }
+            }
+            if chunk.is_empty() {
+                break self.aggregate(state, &mut tally);
+            }
+
+            match self.process_chunk(chunk, threads) {
+                Ok((chunk_state, chunk_tally)) => {
+                    tally.append(chunk_tally);
+                    state = self.timed(tally.elapsed_mut(Stage::Aggregate), || {
+                        self.aggregator.merge(state, chunk_state)
+                    });
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.finish_run::<S>(started, tally, output)
+    }
+
+    fn process_chunk<X>(
+        &self,
+        mut chunk: Vec<(usize, Result<X, PipelineError>)>,
+        threads: NonZeroUsize,
+    ) -> Result<(A::State, Tally), PipelineError>
+    where
+        X: Borrow<T> + Send,
+        F: Sync,
+        P: Sync,
+        A: Sync,
+        A::State: Send,
+    {
+        let entries_per_thread = chunk.len().div_ceil(threads.get());
+        let mut parts = Vec::with_capacity(threads.get());
+        while chunk.len() > entries_per_thread {
+            let rest = chunk.split_off(entries_per_thread);
+            parts.push(chunk);
+            chunk = rest;
+        }
+        parts.push(chunk);
+
+        thread::scope(|scope| {
+            let jobs: Vec<_> = parts
+                .into_iter()
+                .map(|part| {
+                    scope.spawn(move || {
+                        let mut state = self.aggregator.init_part();
+                        let mut tally = Tally::default();
+                        for (position, entry) in part {
+                            self.process_entry(position, entry, &mut state, &mut tally)?;
+                        }
+                        Ok::<_, PipelineError>((state, tally))
+                    })
+                })
+                .collect();
+
+            // Joining in spawn order merges the parts in their original order,
+            // the first failing part holds the error a sequential run would have hit
+            let mut merged = self.aggregator.init_part();
+            let mut tally = Tally::default();
+            for job in jobs {
+                let (state, part_tally) = job.join().expect("Pipeline worker thread panicked")?;
+                tally.append(part_tally);
+                merged = self.timed(tally.elapsed_mut(Stage::Aggregate), || {
+                    self.aggregator.merge(merged, state)
+                });
+            }
+            Ok((merged, tally))
+        })
+    }
+
+    fn start_run(&self) -> Instant {
+        if let Some(observer) = &self.observer {
+            observer.run_started();
+        }
+        Instant::now()
+    }
+}
+
+// `data_processor::datasource::file::LinesSource<BufReader<File>>` is shown as `LinesSource`
+fn short_type_name<S>() -> &'static str {
+    let name = std::any::type_name::<S>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::datasource::{DataSource, IterSource, JsonLinesSource};
+    use crate::pipeline::{
+        CollectVecAggregator, CountAggregator, EvenNumberFilter, GroupByAggregator,
+        MultiplyByTwoTransformer, SlidingWindowAggregator, StringContainsFilter,
+        StringToUpperTransformer, SumAggregator, TumblingWindowAggregator,
+    };
+
+    fn threads(threads: usize) -> NonZeroUsize {
+        NonZeroUsize::new(threads).unwrap()
+    }
+
+    #[test]
+    fn test_parallel_sum_matches_sequential() {
+        let processor = DataProcessor::new(
+            EvenNumberFilter::new(),
+            MultiplyByTwoTransformer::new(),
+            SumAggregator::new(),
+        );
+        let sequential = processor
+            .process_data(IterSource::new(0..20_000))
+            .unwrap()
+            .into_output();
+        let parallel = processor
+            .process_data_parallel(IterSource::new(0..20_000), threads(4))
+            .unwrap()
+            .into_output();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_parallel_collect_keeps_order() {
+        let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+        let data: Vec<&str> = words.iter().map(String::as_str).collect();
+        let source = DataSource::new(&data);
+        let processor = DataProcessor::new(
+            StringContainsFilter::new("7"),
+            StringToUpperTransformer::new(),
+            CollectVecAggregator::new(),
+        );
+
+        let sequential = processor.process_data(&source).unwrap().into_output();
+        let parallel = processor
+            .process_data_parallel(&source, threads(3))
+            .unwrap()
+            .into_output();
+        assert_eq!(parallel, sequential);
+    }
+
+    // Workers get parts that don't start at a window boundary
+    #[test]
+    fn test_parallel_windows_match_sequential() {
+        let processor = DataProcessor::new(
+            EvenNumberFilter::new(),
+            MultiplyByTwoTransformer::new(),
+            GroupByAggregator::new(
+                |n: &u32| n % 3,
+                TumblingWindowAggregator::new(7, SumAggregator::new()),
+            ),
+        );
+        let sequential = processor
+            .process_data(IterSource::new(0..10_000))
+            .unwrap()
+            .into_output();
+        let parallel = processor
+            .process_data_parallel(IterSource::new(0..10_000), threads(3))
+            .unwrap()
+            .into_output();
+        assert_eq!(parallel, sequential);
+
+        let processor = DataProcessor::new(
+            EvenNumberFilter::new(),
+            MultiplyByTwoTransformer::new(),
+            SlidingWindowAggregator::new(5, 3, CollectVecAggregator::new()),
+        );
+        let sequential = processor
+            .process_data(IterSource::new(0..10_000))
+            .unwrap()
+            .into_output();
+        let parallel = processor
+            .process_data_parallel(IterSource::new(0..10_000), threads(3))
+            .unwrap()
+            .into_output();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_parallel_reports_first_error() {
+        let data = ["ab", "", "abc", ""];
+        let source = DataSource::new(&data);
+        let processor = DataProcessor::new(
+            StringContainsFilter::new(""),
+            StringToUpperTransformer::new(),
+            CollectVecAggregator::new(),
+        );
+
+        let sequential = processor.process_data(&source).unwrap_err();
+        let parallel = processor
+            .process_data_parallel(&source, threads(4))
+            .unwrap_err();
+        assert_eq!(format!("{parallel:?}"), format!("{sequential:?}"));
+    }
+
+    #[test]
+    fn test_skip_transform_errors() {
+        let data = ["ab", "", "abc", ""];
+        let source = DataSource::new(&data);
+        let processor = DataProcessor::new(
+            StringContainsFilter::new(""),
+            StringToUpperTransformer::new(),
+            CollectVecAggregator::new(),
+        )
+        .with_transform_policy(ErrorPolicy::Skip);
+
+        let report = processor.process_data(&source).unwrap();
+        assert_eq!(
+            (report.read(), report.aggregated(), report.skipped()),
+            (4, 2, 2)
+        );
+        assert!(report.dead_letters().is_empty());
+        assert_eq!(report.into_output(), vec!["AB", "ABC"]);
+    }
+
+    #[test]
+    fn test_dead_letters() {
+        let input = "2\nnot a number\n4\n6\n";
+        let processor = DataProcessor::new(
+            EvenNumberFilter::new(),
+            MultiplyByTwoTransformer::new(),
+            SumAggregator::<u32>::new(),
+        )
+        .with_source_policy(ErrorPolicy::DeadLetter);
+
+        let report = processor
+            .process_data(JsonLinesSource::new(input.as_bytes()))
+            .unwrap();
+        assert_eq!(*report.output(), 24);
+        let [dead_letter] = report.dead_letters() else {
+            panic!("Expected a single dead letter, got {report}");
+        };
+        assert_eq!(
+            (dead_letter.stage(), dead_letter.position()),
+            (Stage::Source, 2)
+        );
+        assert!(matches!(
+            dead_letter.error(),
+            PipelineError::Json { line: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parallel_dead_letters_in_order() {
+        let words: Vec<String> = (0..5_000)
+            .map(|i| {
+                if i % 7 == 0 {
+                    String::new()
+                } else {
+                    i.to_string()
+                }
+            })
+            .collect();
+        let source = DataSource::new(&words);
+        let processor = DataProcessor::new(
+            StringContainsFilter::new(""),
+            StringToUpperTransformer::new(),
+            CountAggregator::new(),
+        )
+        .with_transform_policy(ErrorPolicy::DeadLetter);
+
+        let sequential = processor.process_data(&source).unwrap();
+        let parallel = processor
+            .process_data_parallel(&source, threads(3))
+            .unwrap();
+        assert_eq!(parallel.output(), sequential.output());
+        assert_eq!(parallel.to_string(), sequential.to_string());
+    }
+
+    #[test]
+    fn test_parallel_empty_source() {
+        let processor = DataProcessor::new(
+            EvenNumberFilter::new(),
+            MultiplyByTwoTransformer::new(),
+            SumAggregator::new(),
+        );
+        let result = processor.process_data_parallel(IterSource::new(0..0), threads(2));
+        assert!(matches!(result, Err(PipelineError::AggregatorError(_))));
+    }
+}
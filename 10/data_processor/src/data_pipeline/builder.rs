@@ -0,0 +1,206 @@
+use crate::datasource::Source;
+use crate::errors::PipelineError;
+use crate::pipeline::{Aggregator, Filter, Transformer};
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// A chain of any number of stages, built one call at a time:
+///
+/// `Pipeline::from(&source).filter(..).map(..).filter(..).aggregate(..)`
+///
+/// `T` is the type the next stage works on. Items from a borrowed source stay borrowed (`&T`),
+/// after a `map` they are owned, stages get a reference either way so nothing is cloned.
+/// Every stage is its own type parameter, so the whole chain is statically dispatched.
+/// Stages are lazy, items flow one at a time through all of them once `aggregate` is called
+pub struct Pipeline<T, I> {
+    items: I,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<S: Source> From<S> for Pipeline<S::Value, S::Items> {
+    fn from(source: S) -> Self {
+        Pipeline::new(source.into_items())
+    }
+}
+
+impl<T, I> Pipeline<T, I> {
+    fn new(items: I) -> Self {
+        Self {
+            items,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, X, I> Pipeline<T, I>
+where
+    T: Debug,
+    X: Borrow<T>,
+    I: Iterator<Item = Result<X, PipelineError>>,
+{
+    /// Keeps only the items `filter` accepts
+    pub fn filter<F>(self, filter: F) -> Pipeline<T, impl Iterator<Item = Result<X, PipelineError>>>
+    where
+        F: Filter<T>,
+    {
+        Pipeline::new(self.items.filter(move |item| match item {
+            Ok(value) => filter.filter(value.borrow()),
+            // Errors are kept so aggregate can report them
+            Err(_) => true,
+        }))
+    }
+
+    /// Turns every item into a `U`, a failed transformation stops the pipeline at `aggregate`
+    pub fn map<U, P>(
+        self,
+        transformer: P,
+    ) -> Pipeline<U, impl Iterator<Item = Result<U, PipelineError>>>
+    where
+        U: Debug,
+        P: Transformer<T, U> + Debug,
+    {
+        Pipeline::new(self.items.map(move |item| {
+            let value = item?;
+            transformer
+                .transform(value.borrow())
+                .ok_or_else(|| Self::transform_error(value.borrow(), &transformer))
+        }))
+    }
+
+    /// Turns every item into any number of `U`s
+    pub fn flat_map<U, J, P>(
+        self,
+        transformer: P,
+    ) -> Pipeline<U, impl Iterator<Item = Result<U, PipelineError>>>
+    where
+        U: Debug,
+        J: IntoIterator<Item = U>,
+        P: Transformer<T, J> + Debug,
+    {
+        Pipeline::new(self.items.flat_map(move |item| {
+            let result = item.and_then(|value| {
+                transformer
+                    .transform(value.borrow())
+                    .ok_or_else(|| Self::transform_error(value.borrow(), &transformer))
+            });
+            // One iterator type for both outcomes: the values, or a single error
+            let (values, error) = match result {
+                Ok(values) => (Some(values), None),
+                Err(e) => (None, Some(e)),
+            };
+            values.into_iter().flatten().map(Ok).chain(error.map(Err))
+        }))
+    }
+
+    fn transform_error<U>(
+        value: &T,
+        transformer: &(impl Transformer<T, U> + Debug),
+    ) -> PipelineError {
+        transformer.failure(value).unwrap_or_else(|| {
+            PipelineError::TransformError(format!(
+                "Could not transform {value:?} using {transformer:?}"
+            ))
+        })
+    }
+}
+
+impl<T, I> Pipeline<T, I>
+where
+    T: Debug,
+    I: Iterator<Item = Result<T, PipelineError>>,
+{
+    /// Runs the pipeline and reduces the items to a single `V`
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError` if an item could not be transformed or the result could not be aggregated
+    // Taken by value like every other stage, so the chain reads the same all the way
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn aggregate<V, A>(self, aggregator: A) -> Result<V, PipelineError>
+    where
+        A: Aggregator<T, V> + Debug,
+    {
+        let mut state = aggregator.init();
+        let mut count = 0;
+        for item in self.items {
+            aggregator.accumulate(&mut state, item?);
+            count += 1;
+        }
+        aggregator.check(&state)?;
+        aggregator.finish(state).ok_or_else(|| {
+            PipelineError::AggregatorError(format!(
+                "Couldn't aggregate {count} items using {aggregator:?}"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::datasource::{DataSource, IterSource, LinesSource};
+    use crate::pipeline::{
+        CollectVecAggregator, EvenNumberFilter, MultiplyByTwoTransformer,
+        SplitWhitespaceTransformer, StringContainsFilter, StringToUpperTransformer, SumAggregator,
+    };
+
+    #[test]
+    fn test_chained_numbers() {
+        let data = [1, 2, 3, 4, 5, 6];
+        let source = DataSource::new(&data);
+        let result = Pipeline::from(&source)
+            .filter(EvenNumberFilter::new())
+            .map(MultiplyByTwoTransformer::new())
+            .map(MultiplyByTwoTransformer::new())
+            .filter(EvenNumberFilter::new())
+            .aggregate(SumAggregator::new());
+        assert_eq!(result.unwrap(), 48);
+    }
+
+    #[test]
+    fn test_flat_map_changes_type() {
+        let data = ["the crab", "a rusty crate", "lab"];
+        let source = DataSource::new(&data);
+        let result = Pipeline::from(&source)
+            .flat_map(SplitWhitespaceTransformer::new())
+            .filter(StringContainsFilter::new("ra"))
+            .map(StringToUpperTransformer::new())
+            .aggregate(CollectVecAggregator::new());
+        assert_eq!(result.unwrap(), vec!["CRAB", "CRATE"]);
+    }
+
+    #[test]
+    fn test_streaming_sources() {
+        let result = Pipeline::from(IterSource::new(1..=100_u32))
+            .filter(EvenNumberFilter::new())
+            .aggregate(SumAggregator::new());
+        assert_eq!(result.unwrap(), 2550);
+
+        let result = Pipeline::from(LinesSource::new("a crab\nno\nthe crate\n".as_bytes()))
+            .map(StringToUpperTransformer::new())
+            .aggregate(CollectVecAggregator::new());
+        assert_eq!(result.unwrap(), vec!["A CRAB", "NO", "THE CRATE"]);
+    }
+
+    #[test]
+    fn test_transform_error_stops_pipeline() {
+        let data = ["abc", ""];
+        let source = DataSource::new(&data);
+        let result = Pipeline::from(&source)
+            .map(StringToUpperTransformer::new())
+            .aggregate(CollectVecAggregator::new());
+        assert!(matches!(result, Err(PipelineError::TransformError(_))));
+    }
+
+    #[test]
+    fn test_aggregate_error_on_empty() {
+        let data = [1, 3];
+        let source = DataSource::new(&data);
+        let result = Pipeline::from(&source)
+            .filter(EvenNumberFilter::new())
+            .map(MultiplyByTwoTransformer::new())
+            .aggregate(SumAggregator::new());
+        assert!(matches!(result, Err(PipelineError::AggregatorError(_))));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::data_pipeline::Stage;
+use crate::errors::PipelineError;
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Gets told how the runs of a `DataProcessor` went, see `DataProcessor::with_observer`
+///
+/// Parallel runs call it from the thread that started the run, but it has to be
+/// `Send + Sync` to be shared with the workers
+pub trait Observer: Send + Sync {
+    fn run_started(&self) {}
+
+    /// Called once per stage in stage order when the run ends, also if it failed
+    fn stage_finished(&self, event: &StageEvent);
+
+    fn run_finished(&self, _elapsed: Duration, _error: Option<&PipelineError>) {}
+}
+
+/// What a single stage did during a run
+///
+/// Items a stage drops on purpose, like the filter does, are not rejects.
+/// Parallel runs add up the time of all workers
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageEvent {
+    stage: Stage,
+    name: String,
+    items_in: usize,
+    items_out: usize,
+    rejected: usize,
+    elapsed: Duration,
+}
+
+impl StageEvent {
+    pub(crate) fn new(
+        stage: Stage,
+        name: String,
+        items: (usize, usize),
+        rejected: usize,
+        elapsed: Duration,
+    ) -> Self {
+        let (items_in, items_out) = items;
+        Self {
+            stage,
+            name,
+            items_in,
+            items_out,
+            rejected,
+            elapsed,
+        }
+    }
+
+    #[must_use]
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// The `Debug` output of the filter, transformer or aggregator, the type of the source
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn items_in(&self) -> usize {
+        self.items_in
+    }
+
+    #[must_use]
+    pub fn items_out(&self) -> usize {
+        self.items_out
+    }
+
+    /// Items the stage failed on and that were skipped or dead lettered
+    #[must_use]
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl fmt::Display for StageEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {} in, {} out, {} rejected in {:?}",
+            self.stage, self.name, self.items_in, self.items_out, self.rejected, self.elapsed
+        )
+    }
+}
+
+// A panicking writer must not take every later run down with it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Writes a line per event, to stderr unless told otherwise
+#[derive(Debug)]
+pub struct LoggingObserver<W> {
+    writer: Mutex<W>,
+}
+
+impl LoggingObserver<io::Stderr> {
+    #[must_use]
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> LoggingObserver<W> {
+    #[must_use]
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Logging is best effort, a failing writer does not fail the run
+    fn log(&self, line: fmt::Arguments<'_>) {
+        let _ = writeln!(lock(&self.writer), "{line}");
+    }
+}
+
+impl<W: Write + Send> Observer for LoggingObserver<W> {
+    fn run_started(&self) {
+        self.log(format_args!("Run started"));
+    }
+
+    fn stage_finished(&self, event: &StageEvent) {
+        self.log(format_args!("{event}"));
+    }
+
+    fn run_finished(&self, elapsed: Duration, error: Option<&PipelineError>) {
+        match error {
+            Some(e) => self.log(format_args!("Run failed after {elapsed:?}: {e}")),
+            None => self.log(format_args!("Run finished in {elapsed:?}")),
+        }
+    }
+}
+
+/// Adds up the events of every run it sees, `summary_table` shows the totals
+#[derive(Debug, Default)]
+pub struct MetricsObserver {
+    metrics: Mutex<Metrics>,
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    runs: usize,
+    failed_runs: usize,
+    elapsed: Duration,
+    // One entry per stage, in stage order
+    stages: Vec<StageEvent>,
+}
+
+impl MetricsObserver {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Totals per stage over all runs so far, in stage order
+    #[must_use]
+    pub fn stages(&self) -> Vec<StageEvent> {
+        lock(&self.metrics).stages.clone()
+    }
+
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        lock(&self.metrics).runs
+    }
+
+    #[must_use]
+    pub fn summary_table(&self) -> String {
+        let metrics = lock(&self.metrics);
+        let mut table = format!(
+            "{:<10} {:<28} {:>10} {:>10} {:>10} {:>12}\n",
+            "stage", "name", "items in", "items out", "rejected", "elapsed"
+        );
+        for event in &metrics.stages {
+            // Writing to a String can't fail
+            let _ = writeln!(
+                table,
+                "{:<10} {:<28} {:>10} {:>10} {:>10} {:>12}",
+                event.stage.to_string(),
+                event.name,
+                event.items_in,
+                event.items_out,
+                event.rejected,
+                format!("{:.3?}", event.elapsed)
+            );
+        }
+        let _ = write!(
+            table,
+            "{} runs, {} failed, {:.3?} in total",
+            metrics.runs, metrics.failed_runs, metrics.elapsed
+        );
+        table
+    }
+}
+
+impl fmt::Display for MetricsObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary_table())
+    }
+}
+
+impl Observer for MetricsObserver {
+    fn stage_finished(&self, event: &StageEvent) {
+        let mut metrics = lock(&self.metrics);
+        match metrics
+            .stages
+            .iter_mut()
+            .find(|total| total.stage == event.stage)
+        {
+            Some(total) => {
+                total.items_in += event.items_in;
+                total.items_out += event.items_out;
+                total.rejected += event.rejected;
+                total.elapsed += event.elapsed;
+                // Runs with different stages only keep the first name
+            }
+            None => metrics.stages.push(event.clone()),
+        }
+    }
+
+    fn run_finished(&self, elapsed: Duration, error: Option<&PipelineError>) {
+        let mut metrics = lock(&self.metrics);
+        metrics.runs += 1;
+        metrics.elapsed += elapsed;
+        if error.is_some() {
+            metrics.failed_runs += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_pipeline::{DataProcessor, ErrorPolicy};
+    use crate::datasource::DataSource;
+    use crate::pipeline::{CollectVecAggregator, StringContainsFilter, StringToUpperTransformer};
+    use std::sync::Arc;
+
+    fn processor(
+        pattern: &'static str,
+    ) -> DataProcessor<
+        &'static str,
+        StringContainsFilter<'static>,
+        String,
+        Vec<String>,
+        CollectVecAggregator<String>,
+        StringToUpperTransformer,
+    > {
+        DataProcessor::new(
+            StringContainsFilter::new(pattern),
+            StringToUpperTransformer::new(),
+            CollectVecAggregator::new(),
+        )
+    }
+
+    #[test]
+    fn test_metrics_observer() {
+        // The transformer fails on the empty strings
+        let data = ["ab", "", "cd", ""];
+        let source = DataSource::new(&data);
+        let metrics = Arc::new(MetricsObserver::new());
+        let processor = processor("")
+            .with_transform_policy(ErrorPolicy::Skip)
+            .with_observer(metrics.clone());
+
+        processor.process_data(&source).unwrap();
+        processor.process_data(&source).unwrap();
+
+        let counts: Vec<_> = metrics
+            .stages()
+            .iter()
+            .map(|event| {
+                (
+                    event.stage(),
+                    event.items_in(),
+                    event.items_out(),
+                    event.rejected(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (Stage::Source, 8, 8, 0),
+                (Stage::Filter, 8, 8, 0),
+                (Stage::Transform, 8, 4, 4),
+                (Stage::Aggregate, 4, 2, 0),
+            ]
+        );
+        assert_eq!(metrics.runs(), 2);
+        assert!(metrics.summary_table().contains("StringToUpperTransformer"));
+    }
+
+    #[test]
+    fn test_logging_observer_reports_failure() {
+        let data = ["a", "", "ab"];
+        let source = DataSource::new(&data);
+        let logger = Arc::new(LoggingObserver::new(Vec::new()));
+        let processor = processor("")
+            .with_observer(logger.clone())
+            .with_transform_policy(ErrorPolicy::FailFast);
+
+        assert!(processor.process_data(&source).is_err());
+        drop(processor);
+
+        let log = String::from_utf8(Arc::into_inner(logger).unwrap().into_inner()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].starts_with("[source] DataSource: 2 in, 2 out, 0 rejected"));
+        // The item that stopped the run was not skipped
+        assert!(lines[3].contains("2 in, 1 out, 0 rejected"));
+        assert!(lines[5].starts_with("Run failed after"));
+    }
+}
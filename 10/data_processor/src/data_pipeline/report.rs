@@ -0,0 +1,239 @@
+use crate::errors::PipelineError;
+use serde::Deserialize;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+/// What a stage does with an item it fails on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    /// Stop the whole run with the error
+    #[default]
+    FailFast,
+    /// Drop the item, the report only counts it
+    Skip,
+    /// Drop the item, the report keeps it together with the error
+    DeadLetter,
+}
+
+/// The stages of a `DataProcessor`, only the source and the transformer can fail on a single item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Source,
+    Filter,
+    Transform,
+    Aggregate,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::Source,
+        Stage::Filter,
+        Stage::Transform,
+        Stage::Aggregate,
+    ];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Source => write!(f, "source"),
+            Stage::Filter => write!(f, "filter"),
+            Stage::Transform => write!(f, "transform"),
+            Stage::Aggregate => write!(f, "aggregate"),
+        }
+    }
+}
+
+/// An item a stage failed on and the reason
+#[derive(Debug)]
+pub struct DeadLetter {
+    stage: Stage,
+    position: usize,
+    item: Option<String>,
+    error: PipelineError,
+}
+
+impl DeadLetter {
+    #[must_use]
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// 1-based position of the item in the source
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Debug representation of the item, `None` if the source could not produce it
+    #[must_use]
+    pub fn item(&self) -> Option<&str> {
+        self.item.as_deref()
+    }
+
+    #[must_use]
+    pub fn error(&self) -> &PipelineError {
+        &self.error
+    }
+}
+
+impl fmt::Display for DeadLetter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "item {} rejected by {}", self.position, self.stage)?;
+        if let Some(item) = &self.item {
+            write!(f, " ({item})")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+/// Output of a run together with what happened to the items on the way
+#[derive(Debug)]
+pub struct Report<V> {
+    output: V,
+    tally: Tally,
+}
+
+impl<V> Report<V> {
+    pub(crate) fn new(output: V, tally: Tally) -> Self {
+        Self { output, tally }
+    }
+
+    #[must_use]
+    pub fn output(&self) -> &V {
+        &self.output
+    }
+
+    #[must_use]
+    pub fn into_output(self) -> V {
+        self.output
+    }
+
+    /// Items read from the source, including the ones it failed on
+    #[must_use]
+    pub fn read(&self) -> usize {
+        self.tally.read
+    }
+
+    /// Items that made it to the aggregator
+    #[must_use]
+    pub fn aggregated(&self) -> usize {
+        self.tally.aggregated
+    }
+
+    /// Items dropped by a stage with the `Skip` policy
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.tally.skipped
+    }
+
+    /// Items dropped by a stage with the `DeadLetter` policy, in source order
+    #[must_use]
+    pub fn dead_letters(&self) -> &[DeadLetter] {
+        &self.tally.dead_letters
+    }
+
+    /// Number of items a stage failed on
+    #[must_use]
+    pub fn rejected(&self) -> usize {
+        self.tally.skipped + self.tally.dead_letters.len()
+    }
+}
+
+impl<V> fmt::Display for Report<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} items read, {} aggregated, {} skipped, {} dead letters",
+            self.read(),
+            self.aggregated(),
+            self.skipped(),
+            self.dead_letters().len()
+        )?;
+        for dead_letter in self.dead_letters() {
+            write!(f, "\n  {dead_letter}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Running counts of a run or a part of it
+#[derive(Debug, Default)]
+pub(crate) struct Tally {
+    pub(crate) read: usize,
+    // Items the source produced, the filter's input
+    pub(crate) produced: usize,
+    pub(crate) passed: usize,
+    pub(crate) aggregated: usize,
+    pub(crate) finished: bool,
+    skipped: usize,
+    dead_letters: Vec<DeadLetter>,
+    // Items every stage skipped or dead lettered, in Stage::ALL order
+    rejected: [usize; 4],
+    // Time spent in every stage, in Stage::ALL order, only measured when observed
+    pub(crate) elapsed: [Duration; 4],
+}
+
+impl Tally {
+    /// Items in and out of a stage
+    pub(crate) fn items(&self, stage: Stage) -> (usize, usize) {
+        match stage {
+            Stage::Source => (self.read, self.produced),
+            Stage::Filter => (self.produced, self.passed),
+            Stage::Transform => (self.passed, self.aggregated),
+            // Everything goes into a single output, if the aggregator had one
+            Stage::Aggregate => (self.aggregated, usize::from(self.finished)),
+        }
+    }
+
+    /// Items `stage` failed on without stopping the run
+    pub(crate) fn rejected(&self, stage: Stage) -> usize {
+        self.rejected[stage as usize]
+    }
+
+    pub(crate) fn elapsed_mut(&mut self, stage: Stage) -> &mut Duration {
+        &mut self.elapsed[stage as usize]
+    }
+
+    /// Applies `policy` to an item `stage` failed on
+    ///
+    /// Returns the error back if the run has to stop
+    pub(crate) fn reject(
+        &mut self,
+        policy: ErrorPolicy,
+        stage: Stage,
+        position: usize,
+        item: Option<&dyn Debug>,
+        error: PipelineError,
+    ) -> Result<(), PipelineError> {
+        match policy {
+            ErrorPolicy::FailFast => return Err(error),
+            ErrorPolicy::Skip => self.skipped += 1,
+            ErrorPolicy::DeadLetter => self.dead_letters.push(DeadLetter {
+                stage,
+                position,
+                item: item.map(|item| format!("{item:?}")),
+                error,
+            }),
+        }
+        self.rejected[stage as usize] += 1;
+        Ok(())
+    }
+
+    /// Adds the counts of the items that came after the ones counted so far
+    pub(crate) fn append(&mut self, mut other: Tally) {
+        self.read += other.read;
+        self.produced += other.produced;
+        self.passed += other.passed;
+        self.aggregated += other.aggregated;
+        for (elapsed, other) in self.elapsed.iter_mut().zip(other.elapsed) {
+            *elapsed += other;
+        }
+        for (rejected, other) in self.rejected.iter_mut().zip(other.rejected) {
+            *rejected += other;
+        }
+        self.skipped += other.skipped;
+        self.dead_letters.append(&mut other.dead_letters);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::errors::PipelineError;
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::iter;
+use std::slice;
+
+mod file;
+mod stream;
+
+pub use file::{CsvSource, JsonLinesSource, LinesSource};
+pub use stream::IterSource;
+
+/// Anything a pipeline can pull its items from, one at a time
+///
+/// Items are produced lazily, so a source can be bigger than memory or still be receiving data
+pub trait Source {
+    /// The type the first stage works on
+    type Value: Debug;
+    /// What the source hands out, either the value itself or a reference to it
+    type Item: Borrow<Self::Value>;
+    type Items: Iterator<Item = Result<Self::Item, PipelineError>>;
+
+    fn into_items(self) -> Self::Items;
+}
+
+pub struct DataSource<'a, T>
+where
+    T: Debug,
+{
+    data: &'a [T],
+}
+
+impl<'a, T> DataSource<'a, T>
+where
+    T: Debug,
+{
+    #[must_use]
+    pub fn new(data: &'a [T]) -> Self {
+        Self { data }
+    }
+
+    #[must_use]
+    // Third rule of elision, thus not needing to declare explicit lifetime 'a
+    pub fn get_data(&self) -> &[T] {
+        self.data
+    }
+}
+
+impl<'s, T: Debug> Source for &'s DataSource<'_, T> {
+    type Value = T;
+    type Item = &'s T;
+    type Items = iter::Map<slice::Iter<'s, T>, fn(&'s T) -> Result<&'s T, PipelineError>>;
+
+    fn into_items(self) -> Self::Items {
+        self.get_data().iter().map(Ok)
+    }
+}
@@ -0,0 +1,290 @@
+use crate::datasource::Source;
+use crate::errors::PipelineError;
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::marker::PhantomData;
+use std::path::Path;
+
+// All file sources read one line or record at a time, memory does not grow with the file size
+
+fn open(path: &Path) -> Result<File, PipelineError> {
+    File::open(path).map_err(|source| PipelineError::Io {
+        context: format!("Could not open {}", path.display()),
+        source,
+    })
+}
+
+/// Every line of the input is an item
+pub struct LinesSource<R> {
+    reader: R,
+}
+
+impl<R: BufRead> LinesSource<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl LinesSource<BufReader<File>> {
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Io` if the file cannot be opened
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+        Ok(Self::new(BufReader::new(open(path.as_ref())?)))
+    }
+}
+
+impl<R: BufRead> Source for LinesSource<R> {
+    type Value = String;
+    type Item = String;
+    type Items = LineItems<R>;
+
+    fn into_items(self) -> Self::Items {
+        LineItems {
+            lines: self.reader.lines(),
+            line_number: 0,
+            failed: false,
+        }
+    }
+}
+
+pub struct LineItems<R> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    failed: bool,
+}
+
+impl<R: BufRead> Iterator for LineItems<R> {
+    type Item = Result<String, PipelineError>;
+
+    // A line that is no UTF-8 is an error of its own, after any other read error
+    // the reader would fail again and again, so the items end there
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let line = self.lines.next()?;
+        self.line_number += 1;
+        Some(line.map_err(|source| {
+            self.failed = source.kind() != io::ErrorKind::InvalidData;
+            PipelineError::Io {
+                context: format!("Could not read line {}", self.line_number),
+                source,
+            }
+        }))
+    }
+}
+
+/// Every record of a CSV input with a header row is deserialized into a `T`
+pub struct CsvSource<R, T> {
+    reader: csv::Reader<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T> CsvSource<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: csv::Reader::from_reader(reader),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> CsvSource<File, T> {
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Io` if the file cannot be opened
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+        // The csv reader does its own buffering
+        Ok(Self::new(open(path.as_ref())?))
+    }
+}
+
+impl<R, T> Source for CsvSource<R, T>
+where
+    R: Read,
+    T: DeserializeOwned + Debug,
+{
+    type Value = T;
+    type Item = T;
+    type Items = CsvItems<R, T>;
+
+    fn into_items(self) -> Self::Items {
+        CsvItems {
+            records: self.reader.into_deserialize(),
+        }
+    }
+}
+
+pub struct CsvItems<R, T> {
+    records: csv::DeserializeRecordsIntoIter<R, T>,
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for CsvItems<R, T> {
+    type Item = Result<T, PipelineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.records.next()?.map_err(PipelineError::from))
+    }
+}
+
+/// Every non empty line of the input is a JSON document deserialized into a `T`
+pub struct JsonLinesSource<R, T> {
+    lines: LinesSource<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T> JsonLinesSource<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: LinesSource::new(reader),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> JsonLinesSource<BufReader<File>, T> {
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Io` if the file cannot be opened
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+        Ok(Self::new(BufReader::new(open(path.as_ref())?)))
+    }
+}
+
+impl<R, T> Source for JsonLinesSource<R, T>
+where
+    R: BufRead,
+    T: DeserializeOwned + Debug,
+{
+    type Value = T;
+    type Item = T;
+    type Items = JsonLineItems<R, T>;
+
+    fn into_items(self) -> Self::Items {
+        JsonLineItems {
+            lines: self.lines.into_items(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub struct JsonLineItems<R, T> {
+    lines: LineItems<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for JsonLineItems<R, T> {
+    type Item = Result<T, PipelineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                serde_json::from_str(&line).map_err(|source| PipelineError::Json {
+                    line: self.lines.line_number,
+                    source,
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: u32,
+    }
+
+    #[test]
+    fn test_lines() {
+        let lines: Vec<String> = LinesSource::new("one\ntwo\n".as_bytes())
+            .into_items()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lines, vec!["one", "two"]);
+    }
+
+    // Fails every read, like a directory opened as a file
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn test_lines_end_at_read_error() {
+        let mut items = LinesSource::new(BufReader::new(Broken)).into_items();
+        assert!(matches!(items.next(), Some(Err(PipelineError::Io { .. }))));
+        assert!(items.next().is_none());
+
+        let items: Vec<_> = LinesSource::new(&b"one\n\xff\ntwo\n"[..])
+            .into_items()
+            .collect();
+        assert_eq!(items.len(), 3);
+        assert!(items[1].is_err() && items[2].is_ok());
+    }
+
+    #[test]
+    fn test_csv() {
+        let input = "sensor,value\na,1\nb,2\n";
+        let readings: Vec<Reading> = CsvSource::new(input.as_bytes())
+            .into_items()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            readings[1],
+            Reading {
+                sensor: "b".to_string(),
+                value: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_csv_invalid_record() {
+        let input = "sensor,value\na,one\n";
+        let mut items = CsvSource::<_, Reading>::new(input.as_bytes()).into_items();
+        assert!(matches!(items.next(), Some(Err(PipelineError::Csv(_)))));
+    }
+
+    #[test]
+    fn test_json_lines_skips_blank_lines() {
+        let input = "{\"sensor\": \"a\", \"value\": 1}\n\n{\"sensor\": \"b\", \"value\": 2}\n";
+        let readings: Vec<Reading> = JsonLinesSource::new(input.as_bytes())
+            .into_items()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(readings.len(), 2);
+    }
+
+    #[test]
+    fn test_json_lines_reports_line_number() {
+        let input = "{\"sensor\": \"a\", \"value\": 1}\nnot json\n";
+        let error = JsonLinesSource::<_, Reading>::new(input.as_bytes())
+            .into_items()
+            .find_map(Result::err);
+        assert!(matches!(error, Some(PipelineError::Json { line: 2, .. })));
+    }
+
+    #[test]
+    fn test_open_missing_file() {
+        let result = LinesSource::open("does/not/exist.txt");
+        assert!(matches!(result, Err(PipelineError::Io { .. })));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::datasource::Source;
+use crate::errors::PipelineError;
+use std::fmt::Debug;
+use std::iter;
+
+/// Feeds a pipeline from any iterator, e.g. a range, a generator or a channel receiver
+pub struct IterSource<I> {
+    items: I,
+}
+
+impl<I> IterSource<I>
+where
+    I: Iterator,
+    I::Item: Debug,
+{
+    pub fn new(items: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            items: items.into_iter(),
+        }
+    }
+}
+
+impl<I> Source for IterSource<I>
+where
+    I: Iterator,
+    I::Item: Debug,
+{
+    type Value = I::Item;
+    type Item = I::Item;
+    type Items = iter::Map<I, fn(I::Item) -> Result<I::Item, PipelineError>>;
+
+    fn into_items(self) -> Self::Items {
+        self.items.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_range() {
+        let items: Vec<u32> = IterSource::new(1..=3)
+            .into_items()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_channel() {
+        let (sender, receiver) = mpsc::channel();
+        let producer = thread::spawn(move || {
+            for i in 0..5 {
+                sender.send(i).unwrap();
+            }
+        });
+        let count = IterSource::new(receiver).into_items().count();
+        producer.join().unwrap();
+        assert_eq!(count, 5);
+    }
+}
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum PipelineError {
+    TransformError(String),
+    AggregatorError(String),
+    SourceError(String),
+    /// Reading a source failed, `context` says what was being read
+    Io {
+        context: String,
+        source: io::Error,
+    },
+    Csv(csv::Error),
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+    Toml(toml::de::Error),
+    /// A described pipeline can't be built, e.g. it names an unknown stage
+    InvalidPipeline(String),
+    InvalidArguments(String),
+    /// A checked numeric stage got a result its type can't hold
+    Overflow {
+        operation: &'static str,
+        type_name: &'static str,
+    },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::TransformError(e)
+            | PipelineError::AggregatorError(e)
+            | PipelineError::SourceError(e) => write!(f, "{e}"),
+            PipelineError::Io { context, source } => write!(f, "{context}: {source}"),
+            // The csv error already mentions the line it failed on
+            PipelineError::Csv(e) => write!(f, "Could not read record: {e}"),
+            PipelineError::Json { line, source } => {
+                write!(f, "Could not parse line {line}: {source}")
+            }
+            PipelineError::Toml(e) => write!(f, "Could not parse TOML: {e}"),
+            PipelineError::InvalidPipeline(e) => write!(f, "invalid pipeline: {e}"),
+            PipelineError::InvalidArguments(e) => write!(f, "invalid arguments: {e}"),
+            PipelineError::Overflow {
+                operation,
+                type_name,
+            } => write!(f, "{operation} overflowed {type_name}"),
+        }
+    }
+}
+
+impl Error for PipelineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PipelineError::Io { source, .. } => Some(source),
+            PipelineError::Csv(e) => Some(e),
+            PipelineError::Json { source, .. } => Some(source),
+            PipelineError::Toml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for PipelineError {
+    fn from(value: csv::Error) -> Self {
+        PipelineError::Csv(value)
+    }
+}
+
+impl From<toml::de::Error> for PipelineError {
+    fn from(value: toml::de::Error) -> Self {
+        PipelineError::Toml(value)
+    }
+}
@@ -0,0 +1,8 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+pub mod cli;
+pub mod data_pipeline;
+pub mod datasource;
+pub mod errors;
+pub mod pipeline;
+pub mod registry;
@@ -1,11 +1,18 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(unused)]
 
-use crate::data_pipeline::DataProcessor;
-use crate::datasource::DataSource;
-use crate::pipeline::{
-    CollectVecAggregator, EvenNumberFilter, MultiplyByTwoTransformer, StringContainsFilter,
-    StringToUpperTransformer, SumAggregator,
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use data_processor::data_pipeline::{
+    DataProcessor, ErrorPolicy, LoggingObserver, MetricsObserver, Pipeline,
+};
+use data_processor::datasource::{DataSource, IterSource};
+use data_processor::pipeline::{
+    CollectVecAggregator, EvenNumberFilter, Filter, GroupByAggregator, MeanAggregator,
+    MultiplyByTwoTransformer, Named, SplitWhitespaceTransformer, StringContainsFilter,
+    StringToUpperTransformer, SumAggregator, Transformer, TumblingWindowAggregator,
+    WideSumAggregator,
 };
 
 fn main() {
@@ -18,7 +25,7 @@ fn main() {
         SumAggregator::new(),
     );
     match data_filter.process_data(&datasource) {
-        Ok(data) => println!("Data processor has the output: {data}"),
+        Ok(report) => println!("Data processor has the output: {}", report.output()),
         Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
     println!("\n\n====================== Strings Pipeline ======================");
@@ -30,268 +37,112 @@ fn main() {
         CollectVecAggregator::new(),
     );
     match data_filter.process_data(&datasource) {
-        Ok(data) => println!("Data processor has the output: {data:?}"),
+        Ok(report) => println!("Data processor has the output: {:?}", report.output()),
         Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
-}
-
-mod errors {
-    #[derive(Debug)]
-    pub enum PipelineError {
-        TransformError(String),
-        AggregatorError(String),
-    }
-}
-
-// This would sit in a separate file like data_pipeline.rs or data_pipeline/mod.rs
-mod data_pipeline {
-    use crate::datasource::DataSource;
-    use crate::errors::PipelineError;
-    use crate::pipeline::{Aggregator, Filter, Transformer};
-    use std::fmt::Debug;
-    use std::marker::PhantomData;
-
-    pub struct DataProcessor<T, F, U, V, A, P>
-    where
-        T: Debug,
-        U: Debug,
-        F: Filter<T> + Debug,
-        P: Transformer<T, U> + Debug,
-        A: Aggregator<U, V> + Debug,
-    {
-        filter: F,
-        transformer: P,
-        aggregator: A,
-        _phantom: PhantomData<(T, U, V)>,
+    println!("\n\n====================== Streaming Pipeline ======================");
+    // The numbers are generated while the pipeline runs, they never exist as a whole
+    let datasource = IterSource::new(1..=1000);
+    let metrics = Arc::new(MetricsObserver::new());
+    let data_filter = DataProcessor::new(
+        EvenNumberFilter::new(),
+        MultiplyByTwoTransformer::new(),
+        SumAggregator::new(),
+    )
+    .with_observer(metrics.clone());
+    match data_filter.process_data(datasource) {
+        Ok(report) => println!("Data processor has the output: {}", report.output()),
+        Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
-
-    impl<T, F, U, V, A, P> DataProcessor<T, F, U, V, A, P>
-    where
-        T: Debug,
-        U: Debug,
-        F: Filter<T> + Debug,
-        P: Transformer<T, U> + Debug,
-        A: Aggregator<U, V> + Debug,
-    {
-        pub fn new(filter: F, transformer: P, aggregator: A) -> Self {
-            Self {
-                filter,
-                transformer,
-                aggregator,
-                _phantom: PhantomData,
-            }
-        }
-
-        fn filter<'a>(&self, data: &'a [T]) -> Vec<&'a T> {
-            let data_len: usize = data.len();
-            let mut filtered_data: Vec<&T> = Vec::with_capacity(data_len);
-
-            for entry in data {
-                if self.filter.filter(entry) {
-                    filtered_data.push(entry);
-                }
-            }
-
-            filtered_data
-        }
-
-        fn transform(&self, data: &[&T]) -> Result<Vec<U>, PipelineError> {
-            let mut transformed_data: Vec<U> = Vec::with_capacity(data.len());
-            for entry in data {
-                let Some(transformed_entry) = self.transformer.transform(entry) else {
-                    return Err(PipelineError::TransformError(format!(
-                        "Could not transform {entry:?} using {:?}",
-                        self.transformer
-                    )));
-                };
-                transformed_data.push(transformed_entry);
-            }
-
-            Ok(transformed_data)
-        }
-
-        fn aggregate(&self, data: &[U]) -> Result<V, PipelineError> {
-            if let Some(output) = self.aggregator.aggregate(data) {
-                Ok(output)
-            } else {
-                Err(PipelineError::AggregatorError(format!(
-                    "Couldn't aggregate data {data:?} using {:?}",
-                    self.aggregator
-                )))
-            }
-        }
-
-        // 0 clone calls ;)
-        pub fn process_data(&self, source: &DataSource<T>) -> Result<V, PipelineError> {
-            let data = source.get_data();
-            let filtered_data = self.filter(data);
-            println!("Filtered data with {:?}: {:?}", self.filter, filtered_data);
-
-            let transformed_data = self.transform(&filtered_data)?;
-            println!(
-                "Transformed data with {:?}: {:?}",
-                self.transformer, transformed_data
-            );
-
-            self.aggregate(&transformed_data)
-        }
+    println!("{metrics}");
+    println!("\n\n====================== Parallel Pipeline ======================");
+    // Same processor, the items are filtered and transformed on 4 threads
+    let datasource = IterSource::new(1..=10_000);
+    let threads = NonZeroUsize::new(4).unwrap();
+    let data_filter = data_filter.with_observer(Arc::new(LoggingObserver::stderr()));
+    match data_filter.process_data_parallel(datasource, threads) {
+        Ok(report) => println!("Data processor has the output: {}", report.output()),
+        Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
+    composed_pipelines();
 }
 
-// This would sit in a separate file like pipeline.rs or pipeline/mod.rs
-mod pipeline {
-
-    use std::fmt::Debug;
-    use std::marker::PhantomData;
-
-    pub trait Filter<T: Debug> {
-        fn filter(&self, item: &T) -> bool;
-    }
-
-    pub trait Transformer<T, U> {
-        fn transform(&self, item: &T) -> Option<U>;
-    }
-
-    pub trait Aggregator<U, V> {
-        fn aggregate(&self, data: &[U]) -> Option<V>;
-    }
-
-    #[derive(Debug)]
-    pub struct CollectVecAggregator<T: Clone> {
-        _marker: PhantomData<T>,
-    }
-
-    impl<T: Clone> CollectVecAggregator<T> {
-        pub fn new() -> Self {
-            Self {
-                _marker: PhantomData,
-            }
-        }
-    }
-
-    impl<T: Clone> Aggregator<T, Vec<T>> for CollectVecAggregator<T> {
-        fn aggregate(&self, data: &[T]) -> Option<Vec<T>> {
-            Some(data.to_vec())
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct StringToUpperTransformer {}
-
-    impl StringToUpperTransformer {
-        pub fn new() -> Self {
-            Self {}
-        }
-    }
-
-    impl Transformer<&str, String> for StringToUpperTransformer {
-        fn transform(&self, item: &&str) -> Option<String> {
-            if item.is_empty() {
-                None
-            } else {
-                Some(item.to_uppercase().to_string())
-            }
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct StringContainsFilter<'a> {
-        pattern: &'a str,
-    }
-
-    impl<'a> StringContainsFilter<'a> {
-        pub fn new(pattern: &'a str) -> Self {
-            Self { pattern }
-        }
-    }
-
-    impl<'a> Filter<&'a str> for StringContainsFilter<'a> {
-        fn filter(&self, item: &&'a str) -> bool {
-            item.contains(self.pattern)
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct EvenNumberFilter {}
-
-    impl EvenNumberFilter {
-        pub fn new() -> Self {
-            Self {}
-        }
-    }
-
-    impl Filter<u32> for EvenNumberFilter {
-        fn filter(&self, item: &u32) -> bool {
-            (*item & 0b0001) == 0
-        }
-    }
-
-    #[derive(Debug)]
-    pub struct MultiplyByTwoTransformer {}
-
-    impl MultiplyByTwoTransformer {
-        pub fn new() -> Self {
-            Self {}
-        }
-    }
-
-    impl Transformer<u32, u32> for MultiplyByTwoTransformer {
-        fn transform(&self, item: &u32) -> Option<u32> {
-            Some(item.saturating_mul(2))
-        }
+// Windows, error policies and closures on top of the basic stages
+fn composed_pipelines() {
+    println!("\n\n====================== Windowed Pipeline ======================");
+    // Mean of every 5 doubled even numbers, per last digit
+    let datasource = IterSource::new(1..=100);
+    let data_filter = DataProcessor::new(
+        EvenNumberFilter::new(),
+        MultiplyByTwoTransformer::new(),
+        GroupByAggregator::new(
+            |n: &u32| n % 10,
+            TumblingWindowAggregator::new(5, MeanAggregator::new()),
+        ),
+    );
+    match data_filter.process_data(datasource) {
+        Ok(report) => println!("Data processor has the output: {:?}", report.output()),
+        Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
-
-    #[derive(Debug)]
-    pub struct SumAggregator {}
-
-    impl SumAggregator {
-        pub fn new() -> Self {
-            Self {}
-        }
+    println!("\n\n====================== Error Tolerant Pipeline ======================");
+    // Empty strings can't be transformed, they are kept aside instead of failing the run
+    let some_data = ["abba", "", "abca", ""];
+    let datasource = DataSource::new(&some_data);
+    let data_filter = DataProcessor::new(
+        StringContainsFilter::new(""),
+        StringToUpperTransformer::new(),
+        CollectVecAggregator::new(),
+    )
+    .with_transform_policy(ErrorPolicy::DeadLetter);
+    match data_filter.process_data(&datasource) {
+        Ok(report) => println!(
+            "Data processor has the output: {:?}\n{report}",
+            report.output()
+        ),
+        Err(e) => println!("Could not process the data. Reason: {e}"),
+    }
+    println!("\n\n====================== Ad-hoc Pipeline ======================");
+    // Closures instead of named stage types, combined with the existing ones
+    let datasource = IterSource::new(1..=30);
+    let data_filter = DataProcessor::new(
+        Named::new("even or 3", EvenNumberFilter::new().or(|n: &u32| *n == 3)),
+        Named::new(
+            "plus one, doubled",
+            (|n: &u32| n.checked_add(1)).then(MultiplyByTwoTransformer::new()),
+        ),
+        SumAggregator::new(),
+    );
+    match data_filter.process_data(datasource) {
+        Ok(report) => println!("Data processor has the output: {}", report.output()),
+        Err(e) => println!("Could not process the data. Reason: {e}"),
     }
-
-    impl Aggregator<u32, u32> for SumAggregator {
-        fn aggregate(&self, data: &[u32]) -> Option<u32> {
-            if data.is_empty() {
-                None
-            } else {
-                let mut sum_aggregation = 0;
-                for num in data {
-                    sum_aggregation += *num;
-                }
-                Some(sum_aggregation)
-            }
-        }
+    println!("\n\n====================== Chained Pipeline ======================");
+    let some_data = ["the crab", "a rusty crate", "abba was here"];
+    let datasource = DataSource::new(&some_data);
+    let result = Pipeline::from(&datasource)
+        .flat_map(SplitWhitespaceTransformer::new())
+        .filter(StringContainsFilter::new("a"))
+        .map(StringToUpperTransformer::new())
+        .aggregate(CollectVecAggregator::new());
+    match result {
+        Ok(data) => println!("Pipeline has the output: {data:?}"),
+        Err(e) => println!("Could not process the data. Reason: {e:?}"),
     }
-}
-
-// This would sit in a separate file like data.rs or data/mod.rs
-mod datasource {
-    use std::fmt::Debug;
 
-    pub struct DataSource<'a, T>
-    where
-        T: Debug,
-    {
-        data: &'a [T],
+    println!("\n\n====================== Overflowing Pipeline ======================");
+    // A u32 can't hold the sum, the checked sum fails and the wide one sums in a u128
+    let some_data = [u32::MAX, 2, u32::MAX];
+    match Pipeline::from(IterSource::new(some_data)).aggregate(SumAggregator::new()) {
+        Ok(data) => println!("Pipeline has the output: {data}"),
+        Err(e) => println!("Could not process the data. Reason: {e}"),
     }
-
-    impl<'a, T> DataSource<'a, T>
-    where
-        T: Debug,
-    {
-        pub fn new(data: &'a [T]) -> Self {
-            Self { data }
-        }
-
-        // Third rule of elision, thus not needing to declare explicit lifetime 'a
-        pub fn get_data(&self) -> &[T] {
-            self.data
-        }
+    match Pipeline::from(IterSource::new(some_data)).aggregate(WideSumAggregator::new()) {
+        Ok(data) => println!("Pipeline has the output: {data}"),
+        Err(e) => println!("Could not process the data. Reason: {e}"),
     }
 }
 
 /*
 Conceptual Questions
 The code speaks for itself
-*/
\ No newline at end of file
+*/
@@ -0,0 +1,232 @@
+use crate::errors::PipelineError;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+mod adapters;
+mod aggregators;
+mod numeric;
+mod windows;
+
+pub use adapters::{And, FoldAggregator, Named, Not, Or, Then};
+pub use aggregators::{
+    CountAggregator, DistinctCountAggregator, HistogramAggregator, MaxAggregator, MeanAggregator,
+    MinAggregator, PercentileAggregator,
+};
+pub use numeric::{
+    EvenNumberFilter, MultiplyByTwoTransformer, Number, OverflowPolicy, SumAggregator,
+    WideSumAggregator, WidenTransformer,
+};
+pub use windows::{
+    GroupByAggregator, GroupState, SlidingWindowAggregator, TumblingWindowAggregator, WindowState,
+};
+
+// Closures work as filters and transformers too, see adapters.rs
+pub trait Filter<T: Debug> {
+    fn filter(&self, item: &T) -> bool;
+
+    /// Keeps the items both filters accept, `other` only sees the ones `self` accepted
+    fn and<G: Filter<T>>(self, other: G) -> And<Self, G>
+    where
+        Self: Sized,
+    {
+        And::new(self, other)
+    }
+
+    /// Keeps the items either filter accepts, `other` only sees the ones `self` rejected
+    fn or<G: Filter<T>>(self, other: G) -> Or<Self, G>
+    where
+        Self: Sized,
+    {
+        Or::new(self, other)
+    }
+
+    /// Keeps the items this filter rejects
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not::new(self)
+    }
+}
+
+pub trait Transformer<T, U> {
+    fn transform(&self, item: &T) -> Option<U>;
+
+    /// Why `transform` failed on `item`, `None` leaves it to a generic `PipelineError::TransformError`
+    fn failure(&self, _item: &T) -> Option<PipelineError> {
+        None
+    }
+
+    /// Feeds the output of this transformer to `next`, fails if either of them fails
+    fn then<W, Q: Transformer<U, W>>(self, next: Q) -> Then<Self, Q, U>
+    where
+        Self: Sized,
+    {
+        Then::new(self, next)
+    }
+}
+
+// Aggregates one item at a time, so a pipeline never has to hold all of its items in memory
+pub trait Aggregator<U, V> {
+    /// Running result, e.g. the sum so far
+    type State;
+
+    fn init(&self) -> Self::State;
+
+    /// State for a part of the input that does not start at the first item,
+    /// parallel runs give one to every worker
+    ///
+    /// Only aggregators that depend on the position of an item, like windows, need to override it
+    fn init_part(&self) -> Self::State {
+        self.init()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U);
+
+    /// Combines the states of two consecutive runs, `left` holds the earlier items
+    /// and `right` usually comes from `init_part`
+    ///
+    /// Must be associative, parallel runs rely on it to give the same result as a sequential one
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State;
+
+    /// `false` if `merge` can't promise that, e.g. a saturating sum of signed numbers
+    /// depends on the order it hits the bounds in, parallel runs then run sequentially
+    fn mergeable(&self) -> bool {
+        true
+    }
+
+    /// Fails the run if the state went bad, e.g. a checked sum overflowed, checked before `finish`
+    ///
+    /// # Errors
+    ///
+    /// Returns the `PipelineError` the run fails with
+    fn check(&self, _state: &Self::State) -> Result<(), PipelineError> {
+        Ok(())
+    }
+
+    /// Produces the result, `None` if there is nothing meaningful to return
+    fn finish(&self, state: Self::State) -> Option<V>;
+
+    fn aggregate<I>(&self, data: I) -> Option<V>
+    where
+        I: IntoIterator<Item = U>,
+        Self: Sized,
+    {
+        let mut state = self.init();
+        for item in data {
+            self.accumulate(&mut state, item);
+        }
+        self.finish(state)
+    }
+}
+
+#[derive(Debug)]
+pub struct CollectVecAggregator<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> CollectVecAggregator<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for CollectVecAggregator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Items are moved into the Vec, no clone needed
+impl<T> Aggregator<T, Vec<T>> for CollectVecAggregator<T> {
+    type State = Vec<T>;
+
+    fn init(&self) -> Self::State {
+        Vec::new()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        state.push(item);
+    }
+
+    // Appending keeps the items in their original order
+    fn merge(&self, mut left: Self::State, mut right: Self::State) -> Self::State {
+        left.append(&mut right);
+        left
+    }
+
+    fn finish(&self, state: Self::State) -> Option<Vec<T>> {
+        Some(state)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StringToUpperTransformer {}
+
+impl StringToUpperTransformer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Transformer<&str, String> for StringToUpperTransformer {
+    fn transform(&self, item: &&str) -> Option<String> {
+        if item.is_empty() {
+            None
+        } else {
+            Some(item.to_uppercase())
+        }
+    }
+}
+
+// Owned strings, e.g. the lines of a LinesSource
+impl Transformer<String, String> for StringToUpperTransformer {
+    fn transform(&self, item: &String) -> Option<String> {
+        self.transform(&item.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SplitWhitespaceTransformer {}
+
+impl SplitWhitespaceTransformer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+// The words borrow from the item, so splitting allocates only the Vec
+impl<'a> Transformer<&'a str, Vec<&'a str>> for SplitWhitespaceTransformer {
+    fn transform(&self, item: &&'a str) -> Option<Vec<&'a str>> {
+        Some(item.split_whitespace().collect())
+    }
+}
+
+#[derive(Debug)]
+pub struct StringContainsFilter<'a> {
+    pattern: &'a str,
+}
+
+impl<'a> StringContainsFilter<'a> {
+    #[must_use]
+    pub fn new(pattern: &'a str) -> Self {
+        Self { pattern }
+    }
+}
+
+impl<'a> Filter<&'a str> for StringContainsFilter<'a> {
+    fn filter(&self, item: &&'a str) -> bool {
+        item.contains(self.pattern)
+    }
+}
+
+impl Filter<String> for StringContainsFilter<'_> {
+    fn filter(&self, item: &String) -> bool {
+        item.contains(self.pattern)
+    }
+}
@@ -0,0 +1,303 @@
+use crate::errors::PipelineError;
+use crate::pipeline::{Aggregator, Filter, Transformer};
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+// Any `|item: &T| -> bool` closure is a filter
+impl<T, F> Filter<T> for F
+where
+    T: Debug,
+    F: Fn(&T) -> bool,
+{
+    fn filter(&self, item: &T) -> bool {
+        self(item)
+    }
+}
+
+// Any `|item: &T| -> Option<U>` closure is a transformer, `None` means it failed
+impl<T, U, F> Transformer<T, U> for F
+where
+    F: Fn(&T) -> Option<U>,
+{
+    fn transform(&self, item: &T) -> Option<U> {
+        self(item)
+    }
+}
+
+/// Gives a stage a name to show as its `Debug` output
+///
+/// Closures have no `Debug`, wrapping them is enough to use them in a `DataProcessor`:
+/// `Named::new("positive", |n: &i32| *n > 0)`
+pub struct Named<S> {
+    name: &'static str,
+    stage: S,
+}
+
+impl<S> Named<S> {
+    #[must_use]
+    pub fn new(name: &'static str, stage: S) -> Self {
+        Self { name, stage }
+    }
+}
+
+impl<S> Debug for Named<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<T: Debug, S: Filter<T>> Filter<T> for Named<S> {
+    fn filter(&self, item: &T) -> bool {
+        self.stage.filter(item)
+    }
+}
+
+impl<T, U, S: Transformer<T, U>> Transformer<T, U> for Named<S> {
+    fn transform(&self, item: &T) -> Option<U> {
+        self.stage.transform(item)
+    }
+
+    fn failure(&self, item: &T) -> Option<PipelineError> {
+        self.stage.failure(item)
+    }
+}
+
+impl<U, V, S: Aggregator<U, V>> Aggregator<U, V> for Named<S> {
+    type State = S::State;
+
+    fn init(&self) -> Self::State {
+        self.stage.init()
+    }
+
+    fn init_part(&self) -> Self::State {
+        self.stage.init_part()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U) {
+        self.stage.accumulate(state, item);
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        self.stage.merge(left, right)
+    }
+
+    fn mergeable(&self) -> bool {
+        self.stage.mergeable()
+    }
+
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        self.stage.check(state)
+    }
+
+    fn finish(&self, state: Self::State) -> Option<V> {
+        self.stage.finish(state)
+    }
+}
+
+/// Folds the items into a copy of `init` with `fold`, parallel runs combine two folds with `merge`
+///
+/// `FoldAggregator::new(0, |sum: &mut u64, n: u32| *sum += u64::from(n), |a, b| a + b)`
+pub struct FoldAggregator<S, F, M> {
+    init: S,
+    fold: F,
+    merge: M,
+}
+
+impl<S, F, M> FoldAggregator<S, F, M> {
+    #[must_use]
+    pub fn new(init: S, fold: F, merge: M) -> Self {
+        Self { init, fold, merge }
+    }
+}
+
+impl<S: Debug, F, M> Debug for FoldAggregator<S, F, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FoldAggregator")
+            .field("init", &self.init)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<U, S, F, M> Aggregator<U, S> for FoldAggregator<S, F, M>
+where
+    S: Clone,
+    F: Fn(&mut S, U),
+    M: Fn(S, S) -> S,
+{
+    type State = S;
+
+    fn init(&self) -> Self::State {
+        self.init.clone()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U) {
+        (self.fold)(state, item);
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        (self.merge)(left, right)
+    }
+
+    fn finish(&self, state: Self::State) -> Option<S> {
+        Some(state)
+    }
+}
+
+/// See `Filter::and`
+#[derive(Debug)]
+pub struct And<F, G> {
+    first: F,
+    second: G,
+}
+
+impl<F, G> And<F, G> {
+    #[must_use]
+    pub fn new(first: F, second: G) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T: Debug, F: Filter<T>, G: Filter<T>> Filter<T> for And<F, G> {
+    fn filter(&self, item: &T) -> bool {
+        self.first.filter(item) && self.second.filter(item)
+    }
+}
+
+/// See `Filter::or`
+#[derive(Debug)]
+pub struct Or<F, G> {
+    first: F,
+    second: G,
+}
+
+impl<F, G> Or<F, G> {
+    #[must_use]
+    pub fn new(first: F, second: G) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T: Debug, F: Filter<T>, G: Filter<T>> Filter<T> for Or<F, G> {
+    fn filter(&self, item: &T) -> bool {
+        self.first.filter(item) || self.second.filter(item)
+    }
+}
+
+/// See `Filter::not`
+#[derive(Debug)]
+pub struct Not<F> {
+    filter: F,
+}
+
+impl<F> Not<F> {
+    #[must_use]
+    pub fn new(filter: F) -> Self {
+        Self { filter }
+    }
+}
+
+impl<T: Debug, F: Filter<T>> Filter<T> for Not<F> {
+    fn filter(&self, item: &T) -> bool {
+        !self.filter.filter(item)
+    }
+}
+
+/// See `Transformer::then`, `U` is the type handed from the first transformer to the second
+pub struct Then<P, Q, U> {
+    first: P,
+    second: Q,
+    _phantom: PhantomData<fn() -> U>,
+}
+
+impl<P, Q, U> Then<P, Q, U> {
+    #[must_use]
+    pub fn new(first: P, second: Q) -> Self {
+        Self {
+            first,
+            second,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+// Derived Debug would want U: Debug as well
+impl<P: Debug, Q: Debug, U> Debug for Then<P, Q, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Then")
+            .field("first", &self.first)
+            .field("second", &self.second)
+            .finish()
+    }
+}
+
+impl<T, U, W, P, Q> Transformer<T, W> for Then<P, Q, U>
+where
+    P: Transformer<T, U>,
+    Q: Transformer<U, W>,
+{
+    fn transform(&self, item: &T) -> Option<W> {
+        self.second.transform(&self.first.transform(item)?)
+    }
+
+    fn failure(&self, item: &T) -> Option<PipelineError> {
+        match self.first.transform(item) {
+            Some(next) => self.second.failure(&next),
+            None => self.first.failure(item),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_pipeline::{DataProcessor, Pipeline};
+    use crate::datasource::{DataSource, IterSource};
+    use crate::pipeline::{EvenNumberFilter, MultiplyByTwoTransformer, SumAggregator};
+
+    #[test]
+    fn test_filter_combinators() {
+        let filter = EvenNumberFilter::new()
+            .and(|n: &u32| *n > 4)
+            .or(|n: &u32| *n == 1);
+        let kept: Vec<u32> = (0..10).filter(|n| filter.filter(n)).collect();
+        assert_eq!(kept, vec![1, 6, 8]);
+
+        let odd = Filter::<u32>::not(EvenNumberFilter::new());
+        assert!(odd.filter(&3) && !odd.filter(&4));
+    }
+
+    #[test]
+    fn test_transformer_then() {
+        let parse_and_double = (|text: &&str| text.parse::<u32>().ok())
+            .then(MultiplyByTwoTransformer::new())
+            .then(|n: &u32| n.checked_add(1));
+        assert_eq!(parse_and_double.transform(&"20"), Some(41));
+        assert_eq!(parse_and_double.transform(&"twenty"), None);
+    }
+
+    #[test]
+    fn test_closures_in_pipeline() {
+        let data = ["3", "4", "x", "10"];
+        let source = DataSource::new(&data);
+        let result = Pipeline::from(&source)
+            .filter(|text: &&str| text.chars().all(|c| c.is_ascii_digit()))
+            .map(Named::new("parse", |text: &&str| text.parse::<u64>().ok()))
+            .aggregate(FoldAggregator::new(
+                0,
+                |sum: &mut u64, n| *sum += n,
+                |a, b| a + b,
+            ));
+        assert_eq!(result.unwrap(), 17);
+    }
+
+    #[test]
+    fn test_closures_in_processor() {
+        let processor = DataProcessor::new(
+            Named::new("multiple of 3", |n: &u32| n.is_multiple_of(3)),
+            Named::new("square", |n: &u32| n.checked_mul(*n)),
+            Named::new("sum", SumAggregator::new()),
+        );
+        let report = processor.process_data(IterSource::new(1..=6)).unwrap();
+        assert_eq!(report.into_output(), 45);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::pipeline::{Aggregator, Number};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Counts the items, an empty input counts as 0
+#[derive(Debug, Default)]
+pub struct CountAggregator {}
+
+impl CountAggregator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T> Aggregator<T, usize> for CountAggregator {
+    type State = usize;
+
+    fn init(&self) -> Self::State {
+        0
+    }
+
+    fn accumulate(&self, state: &mut Self::State, _item: T) {
+        *state += 1;
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        left + right
+    }
+
+    fn finish(&self, state: Self::State) -> Option<usize> {
+        Some(state)
+    }
+}
+
+/// Smallest item, the first one wins a tie
+#[derive(Debug, Default)]
+pub struct MinAggregator {}
+
+impl MinAggregator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T: PartialOrd> Aggregator<T, T> for MinAggregator {
+    type State = Option<T>;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        if state.as_ref().is_none_or(|min| item < *min) {
+            *state = Some(item);
+        }
+    }
+
+    fn merge(&self, mut left: Self::State, right: Self::State) -> Self::State {
+        if let Some(item) = right {
+            self.accumulate(&mut left, item);
+        }
+        left
+    }
+
+    fn finish(&self, state: Self::State) -> Option<T> {
+        state
+    }
+}
+
+/// Biggest item, the first one wins a tie
+#[derive(Debug, Default)]
+pub struct MaxAggregator {}
+
+impl MaxAggregator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T: PartialOrd> Aggregator<T, T> for MaxAggregator {
+    type State = Option<T>;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        if state.as_ref().is_none_or(|max| item > *max) {
+            *state = Some(item);
+        }
+    }
+
+    fn merge(&self, mut left: Self::State, right: Self::State) -> Self::State {
+        if let Some(item) = right {
+            self.accumulate(&mut left, item);
+        }
+        left
+    }
+
+    fn finish(&self, state: Self::State) -> Option<T> {
+        state
+    }
+}
+
+/// Arithmetic mean of the items, an empty input has no mean
+#[derive(Debug, Default)]
+pub struct MeanAggregator {}
+
+impl MeanAggregator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T: Number + Into<f64>> Aggregator<T, f64> for MeanAggregator {
+    // Sum and count so far
+    type State = (f64, usize);
+
+    fn init(&self) -> Self::State {
+        (0.0, 0)
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        state.0 += item.into();
+        state.1 += 1;
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        (left.0 + right.0, left.1 + right.1)
+    }
+
+    // Integers add up exactly in an f64 until the sum passes 2^53, floats get rounded on the way
+    fn mergeable(&self) -> bool {
+        !T::FLOAT
+    }
+
+    // A count above 2^53 items would lose precision, the sum lost it long before
+    #[allow(clippy::cast_precision_loss)]
+    fn finish(&self, (sum, count): Self::State) -> Option<f64> {
+        (count > 0).then(|| sum / count as f64)
+    }
+}
+
+/// The item at the given percentile, using the nearest rank method
+///
+/// Keeps every item until `finish`, an exact percentile can't be had for less
+#[derive(Debug)]
+pub struct PercentileAggregator {
+    percentile: usize,
+}
+
+impl PercentileAggregator {
+    /// # Panics
+    ///
+    /// Panics if `percentile` is above 100
+    #[must_use]
+    pub fn new(percentile: u8) -> Self {
+        assert!(percentile <= 100, "A percentile is between 0 and 100");
+        Self {
+            percentile: usize::from(percentile),
+        }
+    }
+
+    #[must_use]
+    pub fn median() -> Self {
+        Self::new(50)
+    }
+}
+
+impl<T: PartialOrd> Aggregator<T, T> for PercentileAggregator {
+    type State = Vec<T>;
+
+    fn init(&self) -> Self::State {
+        Vec::new()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        state.push(item);
+    }
+
+    fn merge(&self, mut left: Self::State, mut right: Self::State) -> Self::State {
+        left.append(&mut right);
+        left
+    }
+
+    fn finish(&self, mut state: Self::State) -> Option<T> {
+        if state.is_empty() {
+            return None;
+        }
+        // Nearest rank is ceil(percentile / 100 * n), ranks start at 1
+        let index = (self.percentile * state.len())
+            .div_ceil(100)
+            .saturating_sub(1);
+        // Only the item at index has to be in place, no full sort needed
+        state.select_nth_unstable_by(index, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        Some(state.swap_remove(index))
+    }
+}
+
+/// Counts how many items fall into each bucket
+///
+/// `n` ascending bounds make `n + 1` buckets, bucket `i` holds the items
+/// with `bounds[i - 1] <= item < bounds[i]`
+#[derive(Debug)]
+pub struct HistogramAggregator<T> {
+    bounds: Vec<T>,
+}
+
+impl<T: PartialOrd> HistogramAggregator<T> {
+    /// # Panics
+    ///
+    /// Panics if `bounds` are not strictly ascending, items would land in the wrong buckets
+    #[must_use]
+    pub fn new(bounds: Vec<T>) -> Self {
+        assert!(
+            bounds.windows(2).all(|pair| pair[0] < pair[1]),
+            "Histogram bounds have to be sorted and unique"
+        );
+        Self { bounds }
+    }
+}
+
+impl<T: PartialOrd> Aggregator<T, Vec<usize>> for HistogramAggregator<T> {
+    type State = Vec<usize>;
+
+    fn init(&self) -> Self::State {
+        vec![0; self.bounds.len() + 1]
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        let bucket = self.bounds.partition_point(|bound| *bound <= item);
+        state[bucket] += 1;
+    }
+
+    fn merge(&self, mut left: Self::State, right: Self::State) -> Self::State {
+        for (count, other) in left.iter_mut().zip(right) {
+            *count += other;
+        }
+        left
+    }
+
+    fn finish(&self, state: Self::State) -> Option<Vec<usize>> {
+        Some(state)
+    }
+}
+
+/// Counts the different items, keeps one of each in memory
+#[derive(Debug, Default)]
+pub struct DistinctCountAggregator {}
+
+impl DistinctCountAggregator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T: Hash + Eq> Aggregator<T, usize> for DistinctCountAggregator {
+    type State = HashSet<T>;
+
+    fn init(&self) -> Self::State {
+        HashSet::new()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: T) {
+        state.insert(item);
+    }
+
+    fn merge(&self, mut left: Self::State, right: Self::State) -> Self::State {
+        left.extend(right);
+        left
+    }
+
+    fn finish(&self, state: Self::State) -> Option<usize> {
+        Some(state.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_pipeline::DataProcessor;
+    use crate::datasource::IterSource;
+    use crate::pipeline::Named;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_count_min_max() {
+        let data = [4, 1, 7, 1];
+        assert_eq!(CountAggregator::new().aggregate(data), Some(4));
+        assert_eq!(MinAggregator::new().aggregate(data), Some(1));
+        assert_eq!(MaxAggregator::new().aggregate([2.5, -1.0]), Some(2.5));
+        assert_eq!(MinAggregator::new().aggregate(Vec::<u32>::new()), None);
+    }
+
+    #[test]
+    fn test_mean() {
+        assert_eq!(MeanAggregator::new().aggregate([1_u32, 2, 6]), Some(3.0));
+        assert_eq!(MeanAggregator::new().aggregate(Vec::<f32>::new()), None);
+    }
+
+    #[test]
+    fn test_float_mean_parallel_is_sequential() {
+        let items: Vec<f32> = (1..50_000_u16)
+            .map(|i| {
+                if i % 2 == 0 {
+                    f32::from(i) * 1000.0
+                } else {
+                    1.0 / f32::from(i)
+                }
+            })
+            .collect();
+        let processor = DataProcessor::new(
+            Named::new("all", |_: &f32| true),
+            Named::new("identity", |n: &f32| Some(*n)),
+            MeanAggregator::new(),
+        );
+        let source = || IterSource::new(items.iter().copied());
+        let sequential = processor.process_data(source()).unwrap();
+        let parallel = processor
+            .process_data_parallel(source(), NonZeroUsize::new(4).unwrap())
+            .unwrap();
+        assert_eq!(parallel.output().to_bits(), sequential.output().to_bits());
+        assert!(Aggregator::<u32, f64>::mergeable(&MeanAggregator::new()));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let data = [15, 20, 35, 40, 50];
+        assert_eq!(PercentileAggregator::new(0).aggregate(data), Some(15));
+        assert_eq!(PercentileAggregator::new(30).aggregate(data), Some(20));
+        assert_eq!(PercentileAggregator::median().aggregate(data), Some(35));
+        assert_eq!(PercentileAggregator::new(100).aggregate(data), Some(50));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = HistogramAggregator::new(vec![10, 20]);
+        assert_eq!(
+            histogram.aggregate([1, 10, 15, 20, 99]),
+            Some(vec![1, 2, 2])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "sorted and unique")]
+    fn test_histogram_unsorted_bounds() {
+        let _ = HistogramAggregator::new(vec![20, 10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "sorted and unique")]
+    fn test_histogram_repeated_bounds() {
+        let _ = HistogramAggregator::new(vec![10.0, 10.0]);
+    }
+
+    #[test]
+    fn test_distinct_count_merge() {
+        let distinct = DistinctCountAggregator::new();
+        let left = Aggregator::<&str, usize>::init(&distinct);
+        let mut right = distinct.init_part();
+        distinct.accumulate(&mut right, "crab");
+        let mut state = distinct.merge(left, right);
+        distinct.accumulate(&mut state, "crab");
+        distinct.accumulate(&mut state, "crate");
+        assert_eq!(distinct.finish(state), Some(2));
+    }
+}
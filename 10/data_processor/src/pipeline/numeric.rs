@@ -0,0 +1,605 @@
+use crate::errors::PipelineError;
+use crate::pipeline::{Aggregator, Filter, Transformer};
+use serde::Deserialize;
+use std::any::type_name;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+/// The integer and float types the numeric stages work on
+///
+/// Float results count as overflowed once they are no longer finite
+pub trait Number: Copy + PartialOrd + Debug {
+    const ZERO: Self;
+    const TWO: Self;
+
+    /// Unsigned sums only grow, so they reach the largest value no matter how they are split up
+    const UNSIGNED: bool;
+
+    /// Float sums round differently depending on the order they are added up in
+    const FLOAT: bool;
+
+    /// The type a widening stage works in: `u128` for unsigned integers,
+    /// `i128` for signed ones and `f64` for floats
+    type Wide: Number;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    fn checked_mul(self, other: Self) -> Option<Self>;
+
+    #[must_use]
+    fn saturating_add(self, other: Self) -> Self;
+
+    #[must_use]
+    fn saturating_mul(self, other: Self) -> Self;
+
+    fn widen(self) -> Self::Wide;
+
+    fn is_even(self) -> bool;
+}
+
+macro_rules! unsigned {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ZERO: Self = 0;
+            const TWO: Self = 2;
+            const UNSIGNED: bool = true;
+            const FLOAT: bool = false;
+            type Wide = u128;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                <$t>::saturating_add(self, other)
+            }
+
+            fn saturating_mul(self, other: Self) -> Self {
+                <$t>::saturating_mul(self, other)
+            }
+
+            fn widen(self) -> u128 {
+                u128::from(self)
+            }
+
+            fn is_even(self) -> bool {
+                self.is_multiple_of(2)
+            }
+        }
+    )*};
+}
+
+macro_rules! signed {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ZERO: Self = 0;
+            const TWO: Self = 2;
+            const UNSIGNED: bool = false;
+            const FLOAT: bool = false;
+            type Wide = i128;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                <$t>::saturating_add(self, other)
+            }
+
+            fn saturating_mul(self, other: Self) -> Self {
+                <$t>::saturating_mul(self, other)
+            }
+
+            fn widen(self) -> i128 {
+                i128::from(self)
+            }
+
+            fn is_even(self) -> bool {
+                self % 2 == 0
+            }
+        }
+    )*};
+}
+
+macro_rules! float {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ZERO: Self = 0.0;
+            const TWO: Self = 2.0;
+            const UNSIGNED: bool = false;
+            const FLOAT: bool = true;
+            type Wide = f64;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                Some(self + other).filter(|sum| sum.is_finite())
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                Some(self * other).filter(|product| product.is_finite())
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                (self + other).clamp(<$t>::MIN, <$t>::MAX)
+            }
+
+            fn saturating_mul(self, other: Self) -> Self {
+                (self * other).clamp(<$t>::MIN, <$t>::MAX)
+            }
+
+            fn widen(self) -> f64 {
+                f64::from(self)
+            }
+
+            // Only whole numbers can be even
+            #[allow(clippy::float_cmp)]
+            fn is_even(self) -> bool {
+                self % 2.0 == 0.0
+            }
+        }
+    )*};
+}
+
+unsigned!(u8, u16, u32, u64, u128);
+signed!(i8, i16, i32, i64, i128);
+float!(f32, f64);
+
+/// What a numeric stage does when a result does not fit its type
+///
+/// To not overflow at all, widen the items first, see `WidenTransformer` and `WideSumAggregator`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Fail, aggregators fail the run with `PipelineError::Overflow`
+    #[default]
+    Checked,
+    /// Stop at the largest or smallest value of the type
+    Saturating,
+}
+
+impl OverflowPolicy {
+    fn add<N: Number>(self, left: N, right: N) -> Option<N> {
+        match self {
+            OverflowPolicy::Checked => left.checked_add(right),
+            OverflowPolicy::Saturating => Some(left.saturating_add(right)),
+        }
+    }
+
+    fn mul<N: Number>(self, left: N, right: N) -> Option<N> {
+        match self {
+            OverflowPolicy::Checked => left.checked_mul(right),
+            OverflowPolicy::Saturating => Some(left.saturating_mul(right)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EvenNumberFilter {}
+
+impl EvenNumberFilter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<N: Number> Filter<N> for EvenNumberFilter {
+    fn filter(&self, item: &N) -> bool {
+        item.is_even()
+    }
+}
+
+/// Saturates the items whose double overflows, unless told to fail on them
+#[derive(Debug)]
+pub struct MultiplyByTwoTransformer {
+    overflow: OverflowPolicy,
+}
+
+impl MultiplyByTwoTransformer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::saturating()
+    }
+
+    #[must_use]
+    pub fn saturating() -> Self {
+        Self::with_overflow(OverflowPolicy::Saturating)
+    }
+
+    /// Fails on the items whose double overflows with `PipelineError::Overflow`
+    #[must_use]
+    pub fn checked() -> Self {
+        Self::with_overflow(OverflowPolicy::Checked)
+    }
+
+    #[must_use]
+    pub fn with_overflow(overflow: OverflowPolicy) -> Self {
+        Self { overflow }
+    }
+}
+
+impl Default for MultiplyByTwoTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Number> Transformer<N, N> for MultiplyByTwoTransformer {
+    fn transform(&self, item: &N) -> Option<N> {
+        self.overflow.mul(*item, N::TWO)
+    }
+
+    fn failure(&self, _item: &N) -> Option<PipelineError> {
+        Some(PipelineError::Overflow {
+            operation: "double",
+            type_name: type_name::<N>(),
+        })
+    }
+}
+
+/// Turns the items into their `Number::Wide` type, `u32` into `u128` for instance
+pub struct WidenTransformer<N> {
+    _phantom: PhantomData<fn(N)>,
+}
+
+impl<N> WidenTransformer<N> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<N> Default for WidenTransformer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Debug for WidenTransformer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WidenTransformer<{}>", type_name::<N>())
+    }
+}
+
+impl<N: Number> Transformer<N, N::Wide> for WidenTransformer<N> {
+    fn transform(&self, item: &N) -> Option<N::Wide> {
+        Some(item.widen())
+    }
+}
+
+/// Sums the items in their own type, checked unless told to saturate
+///
+/// Whether a signed or float sum overflows depends on the order of the items,
+/// so parallel runs only split up unsigned sums, see `Aggregator::mergeable`
+pub struct SumAggregator<N = u32> {
+    overflow: OverflowPolicy,
+    _phantom: PhantomData<fn(N)>,
+}
+
+impl<N> SumAggregator<N> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_overflow(OverflowPolicy::Checked)
+    }
+
+    #[must_use]
+    pub fn saturating() -> Self {
+        Self::with_overflow(OverflowPolicy::Saturating)
+    }
+
+    #[must_use]
+    pub fn with_overflow(overflow: OverflowPolicy) -> Self {
+        Self {
+            overflow,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<N> Default for SumAggregator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Debug for SumAggregator<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SumAggregator")
+            .field("overflow", &self.overflow)
+            .finish()
+    }
+}
+
+impl<N: Number> Aggregator<N, N> for SumAggregator<N> {
+    // None until the first item, an empty input has no sum.
+    // Some(None) once the sum overflowed, later items can't bring it back
+    type State = Option<Option<N>>;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: N) {
+        let sum = state.get_or_insert(Some(N::ZERO));
+        *sum = sum.and_then(|sum| self.overflow.add(sum, item));
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(
+                left.zip(right)
+                    .and_then(|(left, right)| self.overflow.add(left, right)),
+            ),
+            (left, right) => left.or(right),
+        }
+    }
+
+    fn mergeable(&self) -> bool {
+        N::UNSIGNED
+    }
+
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        match state {
+            Some(None) => Err(PipelineError::Overflow {
+                operation: "sum",
+                type_name: type_name::<N>(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(&self, state: Self::State) -> Option<N> {
+        state.flatten()
+    }
+}
+
+/// Sums the items in their `Number::Wide` type, so `u32` items add up to a `u128`
+///
+/// Only more than 2^96 `u32` items could overflow it, the wide sum is still checked.
+/// Items as wide as the sum, like `i128`, can overflow it in one order and not in another,
+/// parallel runs don't split those up
+pub struct WideSumAggregator<N> {
+    _phantom: PhantomData<fn(N)>,
+}
+
+impl<N> WideSumAggregator<N> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<N> Default for WideSumAggregator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Debug for WideSumAggregator<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WideSumAggregator<{}>", type_name::<N>())
+    }
+}
+
+impl<N: Number> Aggregator<N, N::Wide> for WideSumAggregator<N> {
+    type State = Option<Option<N::Wide>>;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: N) {
+        let sum = state.get_or_insert(Some(N::Wide::ZERO));
+        *sum = sum.and_then(|sum| sum.checked_add(item.widen()));
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(
+                left.zip(right)
+                    .and_then(|(left, right)| left.checked_add(right)),
+            ),
+            (left, right) => left.or(right),
+        }
+    }
+
+    // Narrower integers would need more than 2^64 of them to get near the bounds
+    fn mergeable(&self) -> bool {
+        N::UNSIGNED || (!N::FLOAT && size_of::<N>() < size_of::<N::Wide>())
+    }
+
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        match state {
+            Some(None) => Err(PipelineError::Overflow {
+                operation: "wide sum",
+                type_name: type_name::<N::Wide>(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(&self, state: Self::State) -> Option<N::Wide> {
+        state.flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_pipeline::{DataProcessor, ErrorPolicy};
+    use crate::datasource::IterSource;
+    use crate::pipeline::Named;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_even_over_numbers() {
+        let even = EvenNumberFilter::new();
+        assert!(even.filter(&4_u8) && !even.filter(&-3_i64));
+        assert!(even.filter(&-2.0_f32) && !even.filter(&2.5_f64));
+    }
+
+    #[test]
+    fn test_checked_sum_overflows() {
+        let sum = SumAggregator::<u8>::new();
+        let mut state = sum.init();
+        for n in [200, 100, 0] {
+            sum.accumulate(&mut state, n);
+        }
+        let error = sum.check(&state).unwrap_err();
+        assert_eq!(error.to_string(), "sum overflowed u8");
+        assert_eq!(sum.finish(state), None);
+    }
+
+    #[test]
+    fn test_saturating_sum() {
+        assert_eq!(
+            SumAggregator::saturating().aggregate([i8::MIN, -1, 5]),
+            Some(-123)
+        );
+        assert_eq!(
+            SumAggregator::saturating().aggregate([f32::MAX, f32::MAX]),
+            Some(f32::MAX)
+        );
+        assert_eq!(
+            SumAggregator::<f64>::new().aggregate([0.5, 1.25]),
+            Some(1.75)
+        );
+    }
+
+    #[test]
+    fn test_wide_sum() {
+        let sum = WideSumAggregator::new().aggregate([u64::MAX, u64::MAX, 2]);
+        assert_eq!(sum, Some(2 * u128::from(u64::MAX) + 2));
+        assert_eq!(
+            WideSumAggregator::new().aggregate([-1_i32, i32::MIN]),
+            Some(-2_147_483_649)
+        );
+    }
+
+    #[test]
+    fn test_checked_double_fails_item() {
+        let processor = DataProcessor::new(
+            Named::new("all", |_: &u32| true),
+            MultiplyByTwoTransformer::checked(),
+            SumAggregator::new(),
+        );
+        let items = || IterSource::new([1, u32::MAX, 2]);
+        assert!(matches!(
+            processor.process_data(items()),
+            Err(PipelineError::Overflow {
+                operation: "double",
+                type_name: "u32"
+            })
+        ));
+        let report = processor
+            .with_transform_policy(ErrorPolicy::Skip)
+            .process_data(items())
+            .unwrap();
+        assert_eq!((*report.output(), report.skipped()), (6, 1));
+
+        let saturating = MultiplyByTwoTransformer::new();
+        assert_eq!(saturating.transform(&i16::MIN), Some(i16::MIN));
+    }
+
+    fn sums<N, V, A>(sum: A, items: &[N], threads: usize) -> [Result<V, String>; 2]
+    where
+        N: Number + Send + Sync,
+        V: Copy,
+        A: Aggregator<N, V> + Debug + Sync,
+        A::State: Send,
+    {
+        let processor = DataProcessor::new(
+            Named::new("all", |_: &N| true),
+            Named::new("identity", |n: &N| Some(*n)),
+            sum,
+        );
+        let items = || IterSource::new(items.iter().copied());
+        [
+            processor.process_data(items()),
+            processor.process_data_parallel(items(), NonZeroUsize::new(threads).unwrap()),
+        ]
+        .map(|result| {
+            result
+                .map(|report| *report.output())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    // Where a signed sum hits the bounds depends on how the items are split up
+    #[test]
+    fn test_signed_sum_parallel_is_sequential() {
+        let items: Vec<i8> = [100; 1024].into_iter().chain([-100; 1024]).collect();
+        let [sequential, parallel] = sums(SumAggregator::saturating(), &items, 2);
+        assert_eq!((sequential, parallel), (Ok(-128), Ok(-128)));
+
+        let [sequential, parallel] = sums(SumAggregator::new(), &[0_i8, 50, 100, -100, 0, 0], 3);
+        assert_eq!(sequential, Err("sum overflowed i8".to_string()));
+        assert_eq!(parallel, sequential);
+
+        let [sequential, parallel] = sums(
+            SumAggregator::saturating(),
+            &[f32::MAX, f32::MAX, -f32::MAX],
+            3,
+        );
+        assert_eq!(parallel, sequential);
+
+        let items: Vec<u8> = [200; 2048].into_iter().chain([0; 2048]).collect();
+        let [sequential, parallel] = sums(SumAggregator::saturating(), &items, 4);
+        assert_eq!((sequential, parallel), (Ok(u8::MAX), Ok(u8::MAX)));
+    }
+
+    // Widening does not make float sums associative
+    #[test]
+    fn test_wide_float_sum_parallel_is_sequential() {
+        let items: Vec<f32> = (1..50_000_u16)
+            .map(|i| {
+                if i % 2 == 0 {
+                    f32::from(i) * 1000.0
+                } else {
+                    1.0 / f32::from(i)
+                }
+            })
+            .collect();
+        let [sequential, parallel] = sums(WideSumAggregator::new(), &items, 4);
+        assert_eq!(parallel, sequential);
+
+        let [sequential, parallel] = sums(WideSumAggregator::new(), &[i64::MAX, 1, -2], 3);
+        assert_eq!(
+            (sequential, parallel),
+            (Ok(i128::from(i64::MAX) - 1), Ok(i128::from(i64::MAX) - 1))
+        );
+    }
+
+    #[test]
+    fn test_overflow_fails_run() {
+        let processor = DataProcessor::new(
+            Named::new("all", |_: &u32| true),
+            Named::new("identity", |n: &u32| Some(*n)),
+            SumAggregator::new(),
+        );
+        let items = || IterSource::new((0..3000).map(|_| 3_000_000_u32));
+        for result in [
+            processor.process_data(items()),
+            processor.process_data_parallel(items(), NonZeroUsize::new(3).unwrap()),
+        ] {
+            assert!(matches!(
+                result,
+                Err(PipelineError::Overflow {
+                    operation: "sum",
+                    type_name: "u32"
+                })
+            ));
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use crate::errors::PipelineError;
+use crate::pipeline::Aggregator;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Debug};
+
+/// Aggregates the items of every key on their own, keys come from `key(&item)`
+///
+/// The result maps every key to what `aggregator` made of its items, in key order
+pub struct GroupByAggregator<F, A> {
+    key: F,
+    aggregator: A,
+}
+
+impl<F, A> GroupByAggregator<F, A> {
+    #[must_use]
+    pub fn new(key: F, aggregator: A) -> Self {
+        Self { key, aggregator }
+    }
+}
+
+// Closures have no Debug, the inner aggregator says enough
+impl<F, A: Debug> Debug for GroupByAggregator<F, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupByAggregator")
+            .field("aggregator", &self.aggregator)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Running state of every key seen so far
+pub struct GroupState<K, S> {
+    groups: BTreeMap<K, S>,
+    // New keys of a part get a part state too, see Aggregator::init_part
+    part: bool,
+}
+
+impl<U, K, V, F, A> Aggregator<U, BTreeMap<K, V>> for GroupByAggregator<F, A>
+where
+    K: Ord,
+    F: Fn(&U) -> K,
+    A: Aggregator<U, V>,
+{
+    type State = GroupState<K, A::State>;
+
+    fn init(&self) -> Self::State {
+        GroupState {
+            groups: BTreeMap::new(),
+            part: false,
+        }
+    }
+
+    fn init_part(&self) -> Self::State {
+        GroupState {
+            groups: BTreeMap::new(),
+            part: true,
+        }
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U) {
+        let group = state.groups.entry((self.key)(&item)).or_insert_with(|| {
+            if state.part {
+                self.aggregator.init_part()
+            } else {
+                self.aggregator.init()
+            }
+        });
+        self.aggregator.accumulate(group, item);
+    }
+
+    fn merge(&self, mut left: Self::State, right: Self::State) -> Self::State {
+        for (key, group) in right.groups {
+            // A key the left side never saw starts in the right side
+            let group = match left.groups.remove(&key) {
+                Some(earlier) => self.aggregator.merge(earlier, group),
+                None => group,
+            };
+            left.groups.insert(key, group);
+        }
+        left
+    }
+
+    fn mergeable(&self) -> bool {
+        self.aggregator.mergeable()
+    }
+
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        state
+            .groups
+            .values()
+            .try_for_each(|group| self.aggregator.check(group))
+    }
+
+    /// `None` if the aggregator has no result for one of the keys
+    fn finish(&self, state: Self::State) -> Option<BTreeMap<K, V>> {
+        state
+            .groups
+            .into_iter()
+            .map(|(key, group)| Some((key, self.aggregator.finish(group)?)))
+            .collect()
+    }
+}
+
+/// State of a window aggregator
+///
+/// Windows are cut by the position of the items, a part that does not start at the first item
+/// (see `Aggregator::init_part`) keeps its items until it is merged and its position is known
+pub struct WindowState<U, W> {
+    position: Position<U, W>,
+}
+
+enum Position<U, W> {
+    Start(W),
+    Part(Vec<U>),
+}
+
+/// Windows cut so far, counted from the first item
+///
+/// Closed windows keep their state until `finish`, so `check` still sees them
+pub struct TumblingWindows<S> {
+    closed: Vec<S>,
+    open: S,
+    open_len: usize,
+}
+
+/// Aggregates every `size` consecutive items on their own, the windows do not overlap
+///
+/// The last window holds whatever is left, so it can be shorter
+#[derive(Debug)]
+pub struct TumblingWindowAggregator<A> {
+    size: usize,
+    aggregator: A,
+}
+
+impl<A> TumblingWindowAggregator<A> {
+    /// # Panics
+    ///
+    /// Panics if `size` is 0
+    #[must_use]
+    pub fn new(size: usize, aggregator: A) -> Self {
+        assert!(size > 0, "A window holds at least one item");
+        Self { size, aggregator }
+    }
+}
+
+impl<A> TumblingWindowAggregator<A> {
+    fn start<U, V>(&self) -> TumblingWindows<A::State>
+    where
+        A: Aggregator<U, V>,
+    {
+        TumblingWindows {
+            closed: Vec::new(),
+            open: self.aggregator.init(),
+            open_len: 0,
+        }
+    }
+
+    fn push<U, V>(&self, windows: &mut TumblingWindows<A::State>, item: U)
+    where
+        A: Aggregator<U, V>,
+    {
+        self.aggregator.accumulate(&mut windows.open, item);
+        windows.open_len += 1;
+        if windows.open_len == self.size {
+            self.close(windows);
+        }
+    }
+
+    fn close<U, V>(&self, windows: &mut TumblingWindows<A::State>)
+    where
+        A: Aggregator<U, V>,
+    {
+        let open = std::mem::replace(&mut windows.open, self.aggregator.init());
+        windows.closed.push(open);
+        windows.open_len = 0;
+    }
+
+    fn anchor<U, V>(
+        &self,
+        state: WindowState<U, TumblingWindows<A::State>>,
+    ) -> TumblingWindows<A::State>
+    where
+        A: Aggregator<U, V>,
+    {
+        match state.position {
+            Position::Start(windows) => windows,
+            Position::Part(items) => {
+                let mut windows = self.start();
+                for item in items {
+                    self.push(&mut windows, item);
+                }
+                windows
+            }
+        }
+    }
+}
+
+impl<U, V, A> Aggregator<U, Vec<V>> for TumblingWindowAggregator<A>
+where
+    A: Aggregator<U, V>,
+{
+    type State = WindowState<U, TumblingWindows<A::State>>;
+
+    fn init(&self) -> Self::State {
+        WindowState {
+            position: Position::Start(self.start()),
+        }
+    }
+
+    fn init_part(&self) -> Self::State {
+        WindowState {
+            position: Position::Part(Vec::new()),
+        }
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U) {
+        match &mut state.position {
+            Position::Start(windows) => self.push(windows, item),
+            Position::Part(items) => items.push(item),
+        }
+    }
+
+    // Two states that both start at the first item are put one after the other
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        let position = match (left.position, right.position) {
+            (Position::Part(mut items), Position::Part(more)) => {
+                items.extend(more);
+                Position::Part(items)
+            }
+            (left, Position::Part(items)) => {
+                let mut windows = self.anchor(WindowState { position: left });
+                for item in items {
+                    self.push(&mut windows, item);
+                }
+                Position::Start(windows)
+            }
+            (left, Position::Start(right)) => {
+                let mut windows = self.anchor(WindowState { position: left });
+                if windows.open_len > 0 {
+                    self.close(&mut windows);
+                }
+                windows.closed.extend(right.closed);
+                windows.open = right.open;
+                windows.open_len = right.open_len;
+                Position::Start(windows)
+            }
+        };
+        WindowState { position }
+    }
+
+    // A part holds bare items, it is checked once it is merged
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        match &state.position {
+            Position::Start(windows) => windows
+                .closed
+                .iter()
+                .chain(std::iter::once(&windows.open))
+                .try_for_each(|window| self.aggregator.check(window)),
+            Position::Part(_) => Ok(()),
+        }
+    }
+
+    /// `None` if the aggregator has no result for one of the windows
+    fn finish(&self, state: Self::State) -> Option<Vec<V>> {
+        let mut windows = self.anchor(state);
+        if windows.open_len > 0 {
+            self.close(&mut windows);
+        }
+        windows
+            .closed
+            .into_iter()
+            .map(|window| self.aggregator.finish(window))
+            .collect()
+    }
+}
+
+/// The last `size` items seen, counted from the first item
+///
+/// Closed windows keep their state until `finish`, so `check` still sees them
+pub struct SlidingWindows<U, S> {
+    buffer: VecDeque<U>,
+    seen: usize,
+    closed: Vec<S>,
+}
+
+/// Aggregates the last `size` items every `step` items, the windows overlap when `step < size`
+///
+/// Only full windows count, an input shorter than `size` has no windows.
+/// Every window is aggregated from a copy of its items, so they have to be `Clone`
+#[derive(Debug)]
+pub struct SlidingWindowAggregator<A> {
+    size: usize,
+    step: usize,
+    aggregator: A,
+}
+
+impl<A> SlidingWindowAggregator<A> {
+    /// # Panics
+    ///
+    /// Panics if `size` or `step` is 0
+    #[must_use]
+    pub fn new(size: usize, step: usize, aggregator: A) -> Self {
+        assert!(size > 0, "A window holds at least one item");
+        assert!(step > 0, "A window has to move by at least one item");
+        Self {
+            size,
+            step,
+            aggregator,
+        }
+    }
+
+    fn start<U, S>(&self) -> SlidingWindows<U, S> {
+        SlidingWindows {
+            buffer: VecDeque::with_capacity(self.size),
+            seen: 0,
+            closed: Vec::new(),
+        }
+    }
+
+    fn push<U, V>(&self, windows: &mut SlidingWindows<U, A::State>, item: U)
+    where
+        U: Clone,
+        A: Aggregator<U, V>,
+    {
+        if windows.buffer.len() == self.size {
+            windows.buffer.pop_front();
+        }
+        windows.buffer.push_back(item);
+        windows.seen += 1;
+
+        if windows.buffer.len() == self.size && (windows.seen - self.size).is_multiple_of(self.step)
+        {
+            let mut window = self.aggregator.init();
+            for item in windows.buffer.iter().cloned() {
+                self.aggregator.accumulate(&mut window, item);
+            }
+            windows.closed.push(window);
+        }
+    }
+
+    fn anchor<U, V>(
+        &self,
+        state: WindowState<U, SlidingWindows<U, A::State>>,
+    ) -> SlidingWindows<U, A::State>
+    where
+        U: Clone,
+        A: Aggregator<U, V>,
+    {
+        match state.position {
+            Position::Start(windows) => windows,
+            Position::Part(items) => {
+                let mut windows = self.start();
+                for item in items {
+                    self.push(&mut windows, item);
+                }
+                windows
+            }
+        }
+    }
+}
+
+impl<U, V, A> Aggregator<U, Vec<V>> for SlidingWindowAggregator<A>
+where
+    U: Clone,
+    A: Aggregator<U, V>,
+{
+    type State = WindowState<U, SlidingWindows<U, A::State>>;
+
+    fn init(&self) -> Self::State {
+        WindowState {
+            position: Position::Start(self.start()),
+        }
+    }
+
+    fn init_part(&self) -> Self::State {
+        WindowState {
+            position: Position::Part(Vec::new()),
+        }
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: U) {
+        match &mut state.position {
+            Position::Start(windows) => self.push(windows, item),
+            Position::Part(items) => items.push(item),
+        }
+    }
+
+    // Two states that both start at the first item are put one after the other
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        let position = match (left.position, right.position) {
+            (Position::Part(mut items), Position::Part(more)) => {
+                items.extend(more);
+                Position::Part(items)
+            }
+            (left, Position::Part(items)) => {
+                let mut windows = self.anchor(WindowState { position: left });
+                for item in items {
+                    self.push(&mut windows, item);
+                }
+                Position::Start(windows)
+            }
+            (left, Position::Start(right)) => {
+                let mut windows = self.anchor(WindowState { position: left });
+                windows.closed.extend(right.closed);
+                windows.buffer = right.buffer;
+                windows.seen = right.seen;
+                Position::Start(windows)
+            }
+        };
+        WindowState { position }
+    }
+
+    // A part holds bare items, it is checked once it is merged
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        match &state.position {
+            Position::Start(windows) => windows
+                .closed
+                .iter()
+                .try_for_each(|window| self.aggregator.check(window)),
+            Position::Part(_) => Ok(()),
+        }
+    }
+
+    /// `None` if the aggregator has no result for one of the windows
+    fn finish(&self, state: Self::State) -> Option<Vec<V>> {
+        self.anchor(state)
+            .closed
+            .into_iter()
+            .map(|window| self.aggregator.finish(window))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pipeline::{
+        CollectVecAggregator, CountAggregator, MaxAggregator, MeanAggregator, SumAggregator,
+    };
+
+    #[test]
+    fn test_group_by() {
+        let words = ["crab", "crate", "lab", "cargo", "rust"];
+        let by_letter =
+            GroupByAggregator::new(|word: &&str| word.chars().next(), CountAggregator::new());
+        let result = by_letter.aggregate(words).unwrap();
+        assert_eq!(
+            result,
+            BTreeMap::from([(Some('c'), 3), (Some('l'), 1), (Some('r'), 1)])
+        );
+    }
+
+    #[test]
+    fn test_tumbling_window() {
+        let windows = TumblingWindowAggregator::new(3, SumAggregator::new());
+        assert_eq!(windows.aggregate(1..=7).unwrap(), vec![6, 15, 7]);
+        assert!(windows.aggregate(0..0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let windows = SlidingWindowAggregator::new(3, 1, MaxAggregator::new());
+        assert_eq!(windows.aggregate([1, 5, 2, 0, 3]).unwrap(), vec![5, 5, 3]);
+
+        let windows = SlidingWindowAggregator::new(2, 2, MeanAggregator::new());
+        assert_eq!(windows.aggregate([1, 3, 5, 7, 9]).unwrap(), vec![2.0, 6.0]);
+    }
+
+    // Every split of the input has to give the same windows as a single run
+    #[test]
+    fn test_windows_merge_parts() {
+        let tumbling = TumblingWindowAggregator::new(4, CollectVecAggregator::new());
+        let sliding = SlidingWindowAggregator::new(3, 2, SumAggregator::new());
+
+        for split in 0..=10_u32 {
+            let mut left = tumbling.init();
+            let mut right = tumbling.init_part();
+            (0..split).for_each(|i| tumbling.accumulate(&mut left, i));
+            (split..10).for_each(|i| tumbling.accumulate(&mut right, i));
+            assert_eq!(
+                tumbling.finish(tumbling.merge(left, right)),
+                tumbling.aggregate(0..10)
+            );
+
+            let mut left = sliding.init();
+            let mut right = sliding.init_part();
+            (0..split).for_each(|i| sliding.accumulate(&mut left, i));
+            (split..10).for_each(|i| sliding.accumulate(&mut right, i));
+            assert_eq!(
+                sliding.finish(sliding.merge(left, right)),
+                sliding.aggregate(0..10)
+            );
+        }
+    }
+
+    #[test]
+    fn test_group_by_windows() {
+        let per_parity = GroupByAggregator::new(
+            |n: &u32| n % 2,
+            TumblingWindowAggregator::new(2, SumAggregator::new()),
+        );
+        let mut left = per_parity.init();
+        let mut right = per_parity.init_part();
+        (1..=3).for_each(|i| per_parity.accumulate(&mut left, i));
+        (4..=6).for_each(|i| per_parity.accumulate(&mut right, i));
+        let result = per_parity.finish(per_parity.merge(left, right)).unwrap();
+        assert_eq!(result, BTreeMap::from([(0, vec![6, 6]), (1, vec![4, 5])]));
+    }
+
+    fn checked<U, V, A: Aggregator<U, V>>(aggregator: &A, items: Vec<U>) -> Option<String> {
+        let mut state = aggregator.init();
+        for item in items {
+            aggregator.accumulate(&mut state, item);
+        }
+        match aggregator.check(&state) {
+            Err(PipelineError::Overflow { type_name, .. }) => Some(type_name.to_string()),
+            _ => None,
+        }
+    }
+
+    // An overflow in any group or window fails the run, even in a window closed long ago
+    #[test]
+    fn test_inner_overflow_is_checked() {
+        let items = vec![200_u8, 100, 1, 2, 3];
+        let per_parity = GroupByAggregator::new(|n: &u8| n % 2, SumAggregator::new());
+        let tumbling = TumblingWindowAggregator::new(2, SumAggregator::new());
+        let sliding = SlidingWindowAggregator::new(2, 1, SumAggregator::new());
+        assert_eq!(checked(&per_parity, items.clone()), Some("u8".to_string()));
+        assert_eq!(checked(&tumbling, items.clone()), Some("u8".to_string()));
+        assert_eq!(checked(&sliding, items), Some("u8".to_string()));
+
+        assert_eq!(checked(&tumbling, vec![100_u8, 100, 100, 100]), None);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::data_pipeline::DataProcessor;
+use crate::errors::PipelineError;
+use crate::pipeline::{Aggregator, Filter, Transformer};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+mod builtin;
+mod description;
+mod value;
+
+pub use description::{PipelineDescription, StageDescription};
+pub use value::{Kind, Value, ValueLineItems, ValueLines, ValueType};
+
+/// A filter a described pipeline can use, see `Registry`
+pub trait ValueFilter: Filter<Value> + Debug + Send + Sync {
+    /// Kind of the items the filter works on, `None` for any kind
+    fn kind(&self) -> Option<Kind>;
+}
+
+/// A transformer a described pipeline can use, see `Registry`
+pub trait ValueTransformer: Transformer<Value, Value> + Debug + Send + Sync {
+    /// Kind of the items the transformer works on, `None` for any kind
+    fn input(&self) -> Option<Kind>;
+
+    /// Kind of the items the transformer produces, `None` for the kind it got
+    fn output(&self) -> Option<Kind>;
+}
+
+/// State of a `ValueAggregator`, only the aggregator that made it knows its type
+pub type AnyState = Box<dyn Any + Send>;
+
+/// An aggregator a described pipeline can use, see `Registry`
+///
+/// Mirrors `Aggregator` with the state type erased
+pub trait ValueAggregator: Debug + Send + Sync {
+    /// Kind of the items the aggregator works on, `None` for any kind
+    fn input(&self) -> Option<Kind>;
+
+    fn init(&self) -> AnyState;
+
+    fn init_part(&self) -> AnyState;
+
+    fn accumulate(&self, state: &mut AnyState, item: Value);
+
+    fn merge(&self, left: AnyState, right: AnyState) -> AnyState;
+
+    fn mergeable(&self) -> bool;
+
+    /// # Errors
+    ///
+    /// See `Aggregator::check`
+    fn check(&self, state: &AnyState) -> Result<(), PipelineError>;
+
+    fn finish(&self, state: AnyState) -> Option<Value>;
+}
+
+/// Makes a typed stage usable in a described pipeline
+///
+/// `T` is the item type the stage takes, `U` the one it produces:
+/// `Typed::<_, u32, u32>::new(MultiplyByTwoTransformer::new())`
+pub struct Typed<S, T, U = T> {
+    stage: S,
+    _phantom: PhantomData<fn(T) -> U>,
+}
+
+impl<S, T, U> Typed<S, T, U> {
+    #[must_use]
+    pub fn new(stage: S) -> Self {
+        Self {
+            stage,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+// Shows as the stage it wraps
+impl<S: Debug, T, U> Debug for Typed<S, T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.stage.fmt(f)
+    }
+}
+
+// Items of another kind never pass, building a pipeline already rules them out
+impl<S, T, U> Filter<Value> for Typed<S, T, U>
+where
+    T: ValueType + Debug,
+    S: Filter<T>,
+{
+    fn filter(&self, item: &Value) -> bool {
+        T::from_value_ref(item).is_some_and(|item| self.stage.filter(item))
+    }
+}
+
+impl<S, T, U> ValueFilter for Typed<S, T, U>
+where
+    T: ValueType + Debug,
+    S: Filter<T> + Debug + Send + Sync,
+{
+    fn kind(&self) -> Option<Kind> {
+        T::KIND
+    }
+}
+
+impl<S, T, U> Transformer<Value, Value> for Typed<S, T, U>
+where
+    T: ValueType,
+    U: ValueType,
+    S: Transformer<T, U>,
+{
+    fn transform(&self, item: &Value) -> Option<Value> {
+        self.stage
+            .transform(T::from_value_ref(item)?)
+            .map(U::into_value)
+    }
+
+    fn failure(&self, item: &Value) -> Option<PipelineError> {
+        self.stage.failure(T::from_value_ref(item)?)
+    }
+}
+
+impl<S, T, U> ValueTransformer for Typed<S, T, U>
+where
+    T: ValueType,
+    U: ValueType,
+    S: Transformer<T, U> + Debug + Send + Sync,
+{
+    fn input(&self) -> Option<Kind> {
+        T::KIND
+    }
+
+    fn output(&self) -> Option<Kind> {
+        U::KIND
+    }
+}
+
+impl<S, T, U> Typed<S, T, U>
+where
+    S: Aggregator<T, U>,
+    S::State: 'static,
+{
+    fn state(state: &mut AnyState) -> &mut S::State {
+        state
+            .downcast_mut()
+            .expect("a typed aggregator only gets the states it made")
+    }
+
+    fn into_state(state: AnyState) -> S::State {
+        *state
+            .downcast()
+            .expect("a typed aggregator only gets the states it made")
+    }
+}
+
+impl<S, T, U> ValueAggregator for Typed<S, T, U>
+where
+    T: ValueType,
+    U: ValueType,
+    S: Aggregator<T, U> + Debug + Send + Sync,
+    S::State: Send + 'static,
+{
+    fn input(&self) -> Option<Kind> {
+        T::KIND
+    }
+
+    fn init(&self) -> AnyState {
+        Box::new(self.stage.init())
+    }
+
+    fn init_part(&self) -> AnyState {
+        Box::new(self.stage.init_part())
+    }
+
+    fn accumulate(&self, state: &mut AnyState, item: Value) {
+        if let Some(item) = T::from_value(item) {
+            self.stage.accumulate(Self::state(state), item);
+        }
+    }
+
+    fn merge(&self, left: AnyState, right: AnyState) -> AnyState {
+        Box::new(
+            self.stage
+                .merge(Self::into_state(left), Self::into_state(right)),
+        )
+    }
+
+    fn mergeable(&self) -> bool {
+        self.stage.mergeable()
+    }
+
+    fn check(&self, state: &AnyState) -> Result<(), PipelineError> {
+        let state = state
+            .downcast_ref()
+            .expect("a typed aggregator only gets the states it made");
+        self.stage.check(state)
+    }
+
+    fn finish(&self, state: AnyState) -> Option<Value> {
+        self.stage
+            .finish(Self::into_state(state))
+            .map(U::into_value)
+    }
+}
+
+impl Aggregator<Value, Value> for Box<dyn ValueAggregator + '_> {
+    type State = AnyState;
+
+    fn init(&self) -> Self::State {
+        (**self).init()
+    }
+
+    fn init_part(&self) -> Self::State {
+        (**self).init_part()
+    }
+
+    fn accumulate(&self, state: &mut Self::State, item: Value) {
+        (**self).accumulate(state, item);
+    }
+
+    fn merge(&self, left: Self::State, right: Self::State) -> Self::State {
+        (**self).merge(left, right)
+    }
+
+    fn mergeable(&self) -> bool {
+        (**self).mergeable()
+    }
+
+    fn check(&self, state: &Self::State) -> Result<(), PipelineError> {
+        (**self).check(state)
+    }
+
+    fn finish(&self, state: Self::State) -> Option<Value> {
+        (**self).finish(state)
+    }
+}
+
+/// The filters of a described pipeline, an item has to pass all of them
+#[derive(Debug)]
+pub struct FilterChain<'a>(Vec<Box<dyn ValueFilter + 'a>>);
+
+impl Filter<Value> for FilterChain<'_> {
+    fn filter(&self, item: &Value) -> bool {
+        self.0.iter().all(|filter| filter.filter(item))
+    }
+}
+
+/// The transformers of a described pipeline, applied in order
+///
+/// There is at least one, passing an item on unchanged would take a copy of it
+#[derive(Debug)]
+pub struct TransformerChain<'a> {
+    first: Box<dyn ValueTransformer + 'a>,
+    rest: Vec<Box<dyn ValueTransformer + 'a>>,
+}
+
+impl Transformer<Value, Value> for TransformerChain<'_> {
+    fn transform(&self, item: &Value) -> Option<Value> {
+        self.rest
+            .iter()
+            .try_fold(self.first.transform(item)?, |item, transformer| {
+                transformer.transform(&item)
+            })
+    }
+
+    // Only runs once an item failed, so the copies don't slow down the items that pass
+    fn failure(&self, item: &Value) -> Option<PipelineError> {
+        let Some(mut item) = self.first.transform(item) else {
+            return self.first.failure(item);
+        };
+        for transformer in &self.rest {
+            match transformer.transform(&item) {
+                Some(next) => item = next,
+                None => return transformer.failure(&item),
+            }
+        }
+        None
+    }
+}
+
+/// A `DataProcessor` built from a `PipelineDescription`
+pub type DescribedPipeline<'a> = DataProcessor<
+    Value,
+    FilterChain<'a>,
+    Value,
+    Value,
+    Box<dyn ValueAggregator + 'a>,
+    TransformerChain<'a>,
+>;
+
+/// Builds a filter from its description, e.g. from its parameters
+pub type FilterFactory =
+    for<'a> fn(&'a StageDescription) -> Result<Box<dyn ValueFilter + 'a>, PipelineError>;
+
+pub type TransformerFactory =
+    for<'a> fn(&'a StageDescription) -> Result<Box<dyn ValueTransformer + 'a>, PipelineError>;
+
+pub type AggregatorFactory =
+    for<'a> fn(&'a StageDescription) -> Result<Box<dyn ValueAggregator + 'a>, PipelineError>;
+
+/// The stages a `PipelineDescription` can name
+///
+/// `new` knows the stages of `pipeline`, more can be added with the `register_*` methods
+#[derive(Debug, Clone)]
+pub struct Registry {
+    filters: BTreeMap<&'static str, FilterFactory>,
+    transformers: BTreeMap<&'static str, TransformerFactory>,
+    aggregators: BTreeMap<&'static str, AggregatorFactory>,
+}
+
+impl Registry {
+    #[must_use]
+    pub fn new() -> Self {
+        let mut registry = Self {
+            filters: BTreeMap::new(),
+            transformers: BTreeMap::new(),
+            aggregators: BTreeMap::new(),
+        };
+        registry.register_filter("even", builtin::even);
+        registry.register_filter("contains", builtin::contains);
+        registry.register_transformer("to_upper", builtin::to_upper);
+        registry.register_transformer("double", builtin::double);
+        registry.register_aggregator("sum", builtin::sum);
+        registry.register_aggregator("collect", builtin::collect);
+        registry.register_aggregator("min", builtin::min);
+        registry.register_aggregator("max", builtin::max);
+        registry
+    }
+
+    /// Replaces any filter registered under the same name
+    pub fn register_filter(&mut self, name: &'static str, factory: FilterFactory) {
+        self.filters.insert(name, factory);
+    }
+
+    /// Replaces any transformer registered under the same name
+    pub fn register_transformer(&mut self, name: &'static str, factory: TransformerFactory) {
+        self.transformers.insert(name, factory);
+    }
+
+    /// Replaces any aggregator registered under the same name
+    pub fn register_aggregator(&mut self, name: &'static str, factory: AggregatorFactory) {
+        self.aggregators.insert(name, factory);
+    }
+
+    pub(crate) fn filter<'a>(
+        &self,
+        description: &'a StageDescription,
+    ) -> Result<Box<dyn ValueFilter + 'a>, PipelineError> {
+        lookup("filter", &self.filters, description)?(description)
+    }
+
+    pub(crate) fn transformer<'a>(
+        &self,
+        description: &'a StageDescription,
+    ) -> Result<Box<dyn ValueTransformer + 'a>, PipelineError> {
+        lookup("transformer", &self.transformers, description)?(description)
+    }
+
+    pub(crate) fn aggregator<'a>(
+        &self,
+        description: &'a StageDescription,
+    ) -> Result<Box<dyn ValueAggregator + 'a>, PipelineError> {
+        lookup("aggregator", &self.aggregators, description)?(description)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lookup<F: Copy>(
+    what: &str,
+    factories: &BTreeMap<&'static str, F>,
+    description: &StageDescription,
+) -> Result<F, PipelineError> {
+    factories.get(description.name()).copied().ok_or_else(|| {
+        let known: Vec<&str> = factories.keys().copied().collect();
+        PipelineError::InvalidPipeline(format!(
+            "unknown {what} {:?}, known are: {}",
+            description.name(),
+            known.join(", ")
+        ))
+    })
+}
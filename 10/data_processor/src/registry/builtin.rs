@@ -0,0 +1,69 @@
+// The factories have to return a Result even where they can't fail
+#![allow(clippy::unnecessary_wraps)]
+
+use crate::errors::PipelineError;
+use crate::pipeline::{
+    CollectVecAggregator, EvenNumberFilter, MaxAggregator, MinAggregator, MultiplyByTwoTransformer,
+    OverflowPolicy, StringContainsFilter, StringToUpperTransformer, SumAggregator,
+};
+use crate::registry::{
+    StageDescription, Typed, Value, ValueAggregator, ValueFilter, ValueTransformer,
+};
+
+pub(super) fn even(_: &StageDescription) -> Result<Box<dyn ValueFilter + '_>, PipelineError> {
+    Ok(Box::new(Typed::<_, u32>::new(EvenNumberFilter::new())))
+}
+
+pub(super) fn contains(
+    description: &StageDescription,
+) -> Result<Box<dyn ValueFilter + '_>, PipelineError> {
+    let pattern = description.str_param("pattern")?;
+    Ok(Box::new(Typed::<_, String>::new(
+        StringContainsFilter::new(pattern),
+    )))
+}
+
+pub(super) fn to_upper(
+    _: &StageDescription,
+) -> Result<Box<dyn ValueTransformer + '_>, PipelineError> {
+    Ok(Box::new(Typed::<_, String>::new(
+        StringToUpperTransformer::new(),
+    )))
+}
+
+pub(super) fn double(
+    description: &StageDescription,
+) -> Result<Box<dyn ValueTransformer + '_>, PipelineError> {
+    // Saturates like MultiplyByTwoTransformer::new unless the description says otherwise
+    let overflow = description
+        .param("overflow")?
+        .unwrap_or(OverflowPolicy::Saturating);
+    Ok(Box::new(Typed::<_, u32>::new(
+        MultiplyByTwoTransformer::with_overflow(overflow),
+    )))
+}
+
+pub(super) fn sum(
+    description: &StageDescription,
+) -> Result<Box<dyn ValueAggregator + '_>, PipelineError> {
+    let overflow = description.param("overflow")?.unwrap_or_default();
+    Ok(Box::new(Typed::<_, u32>::new(
+        SumAggregator::with_overflow(overflow),
+    )))
+}
+
+pub(super) fn collect(
+    _: &StageDescription,
+) -> Result<Box<dyn ValueAggregator + '_>, PipelineError> {
+    Ok(Box::new(Typed::<_, Value, Vec<Value>>::new(
+        CollectVecAggregator::new(),
+    )))
+}
+
+pub(super) fn min(_: &StageDescription) -> Result<Box<dyn ValueAggregator + '_>, PipelineError> {
+    Ok(Box::new(Typed::<_, Value>::new(MinAggregator::new())))
+}
+
+pub(super) fn max(_: &StageDescription) -> Result<Box<dyn ValueAggregator + '_>, PipelineError> {
+    Ok(Box::new(Typed::<_, Value>::new(MaxAggregator::new())))
+}
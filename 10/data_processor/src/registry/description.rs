@@ -0,0 +1,306 @@
+use crate::data_pipeline::{DataProcessor, ErrorPolicy};
+use crate::errors::PipelineError;
+use crate::registry::{DescribedPipeline, FilterChain, Kind, Registry, TransformerChain};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A pipeline written down as TOML or JSON
+///
+/// ```toml
+/// input = "number"
+/// on_source_error = "dead_letter"
+///
+/// [[filters]]
+/// stage = "even"
+///
+/// [[transformers]]
+/// stage = "double"
+///
+/// [aggregator]
+/// stage = "sum"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineDescription {
+    input: Kind,
+    #[serde(default)]
+    filters: Vec<StageDescription>,
+    #[serde(default)]
+    transformers: Vec<StageDescription>,
+    aggregator: StageDescription,
+    #[serde(default)]
+    on_source_error: ErrorPolicy,
+    #[serde(default)]
+    on_transform_error: ErrorPolicy,
+}
+
+/// A stage of a `PipelineDescription`, its name in the `Registry` and its parameters
+#[derive(Debug, Deserialize)]
+pub struct StageDescription {
+    stage: String,
+    #[serde(flatten)]
+    params: BTreeMap<String, serde_json::Value>,
+}
+
+impl StageDescription {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.stage
+    }
+
+    /// # Errors
+    ///
+    /// Returns `PipelineError::InvalidPipeline` if the parameter is missing or not a string
+    pub fn str_param(&self, param: &str) -> Result<&str, PipelineError> {
+        self.params
+            .get(param)
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| {
+                PipelineError::InvalidPipeline(format!(
+                    "{} needs a string parameter {param:?}",
+                    self.stage
+                ))
+            })
+    }
+
+    /// An optional parameter of any type serde can read, e.g. `overflow = "saturating"`
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError::InvalidPipeline` if the parameter has the wrong type
+    pub fn param<T: DeserializeOwned>(&self, param: &str) -> Result<Option<T>, PipelineError> {
+        self.params
+            .get(param)
+            .map(|value| {
+                T::deserialize(value).map_err(|e| {
+                    PipelineError::InvalidPipeline(format!(
+                        "parameter {param:?} of {}: {e}",
+                        self.stage
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    // The kind the stage works on has to be the kind of the items it gets
+    fn check_kind(&self, expected: Option<Kind>, actual: Kind) -> Result<(), PipelineError> {
+        match expected {
+            Some(expected) if expected != actual => Err(PipelineError::InvalidPipeline(format!(
+                "{} works on {expected} items, but gets {actual} items",
+                self.stage
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PipelineDescription {
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Toml` if `toml` is not a pipeline description
+    pub fn from_toml(toml: &str) -> Result<Self, PipelineError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Json` if `json` is not a pipeline description
+    pub fn from_json(json: &str) -> Result<Self, PipelineError> {
+        serde_json::from_str(json).map_err(|source| PipelineError::Json {
+            line: source.line(),
+            source,
+        })
+    }
+
+    /// Reads a `.toml` or a `.json` description
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Io` if the file cannot be read, `PipelineError::InvalidPipeline`
+    /// if it has another extension, or the error of `from_toml` or `from_json`
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|source| PipelineError::Io {
+            context: format!("Could not read {}", path.display()),
+            source,
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(PipelineError::InvalidPipeline(format!(
+                "{} is neither a .toml nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Kind of the items the source has to produce
+    #[must_use]
+    pub fn input(&self) -> Kind {
+        self.input
+    }
+
+    /// Looks up every stage in `registry` and checks that each one gets the kind of items it works on
+    ///
+    /// # Errors
+    ///
+    /// Returns `PipelineError::InvalidPipeline` if there is no transformer, if a stage is unknown,
+    /// has wrong parameters or gets items of the wrong kind
+    pub fn build<'a>(
+        &'a self,
+        registry: &Registry,
+    ) -> Result<DescribedPipeline<'a>, PipelineError> {
+        if self.input == Kind::List {
+            return Err(PipelineError::InvalidPipeline(
+                "the input can't be a list".to_string(),
+            ));
+        }
+
+        let filters = self
+            .filters
+            .iter()
+            .map(|description| {
+                let filter = registry.filter(description)?;
+                description.check_kind(filter.kind(), self.input)?;
+                Ok(filter)
+            })
+            .collect::<Result<_, PipelineError>>()?;
+
+        let mut kind = self.input;
+        let mut transformers: Vec<_> = self
+            .transformers
+            .iter()
+            .map(|description| {
+                let transformer = registry.transformer(description)?;
+                description.check_kind(transformer.input(), kind)?;
+                kind = transformer.output().unwrap_or(kind);
+                Ok(transformer)
+            })
+            .collect::<Result<_, PipelineError>>()?;
+        if transformers.is_empty() {
+            return Err(PipelineError::InvalidPipeline(
+                "a pipeline needs at least one transformer".to_string(),
+            ));
+        }
+        let first = transformers.remove(0);
+
+        let aggregator = registry.aggregator(&self.aggregator)?;
+        self.aggregator.check_kind(aggregator.input(), kind)?;
+
+        Ok(DataProcessor::new(
+            FilterChain(filters),
+            TransformerChain {
+                first,
+                rest: transformers,
+            },
+            aggregator,
+        )
+        .with_source_policy(self.on_source_error)
+        .with_transform_policy(self.on_transform_error))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registry::{Value, ValueLines};
+
+    const EVEN_SUM: &str = r#"
+        input = "number"
+        on_source_error = "skip"
+
+        [[filters]]
+        stage = "even"
+
+        [[transformers]]
+        stage = "double"
+
+        [aggregator]
+        stage = "sum"
+    "#;
+
+    #[test]
+    fn test_toml_description() {
+        let description = PipelineDescription::from_toml(EVEN_SUM).unwrap();
+        let processor = description.build(&Registry::new()).unwrap();
+        let source = ValueLines::new("1\n2\nthree\n4\n".as_bytes(), description.input());
+
+        let report = processor.process_data(source).unwrap();
+        assert_eq!(report.output(), &Value::Number(12));
+        assert_eq!(report.skipped(), 1);
+    }
+
+    #[test]
+    fn test_json_description() {
+        let description = PipelineDescription::from_json(
+            r#"{
+                "input": "text",
+                "filters": [{ "stage": "contains", "pattern": "crab" }],
+                "transformers": [{ "stage": "to_upper" }],
+                "aggregator": { "stage": "collect" }
+            }"#,
+        )
+        .unwrap();
+        let processor = description.build(&Registry::new()).unwrap();
+        let source = ValueLines::new("crabs\nfish\nhermit crab\n".as_bytes(), Kind::Text);
+
+        let report = processor.process_data(source).unwrap();
+        assert_eq!(
+            serde_json::to_string(report.output()).unwrap(),
+            r#"["CRABS","HERMIT CRAB"]"#
+        );
+    }
+
+    #[test]
+    fn test_unknown_stage() {
+        let description =
+            PipelineDescription::from_toml(&EVEN_SUM.replace("\"sum\"", "\"avg\"")).unwrap();
+        let error = description.build(&Registry::new()).err().unwrap();
+        assert!(
+            matches!(&error, PipelineError::InvalidPipeline(e) if e.contains("unknown aggregator \"avg\""))
+        );
+    }
+
+    #[test]
+    fn test_kind_mismatch() {
+        let description =
+            PipelineDescription::from_toml(&EVEN_SUM.replace("\"double\"", "\"to_upper\""))
+                .unwrap();
+        let error = description.build(&Registry::new()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid pipeline: to_upper works on text items, but gets number items"
+        );
+    }
+
+    #[test]
+    fn test_missing_parameter() {
+        let description = PipelineDescription::from_json(
+            r#"{ "input": "text", "filters": [{ "stage": "contains" }], "aggregator": { "stage": "max" } }"#,
+        )
+        .unwrap();
+        assert!(description.build(&Registry::new()).is_err());
+    }
+
+    #[test]
+    fn test_no_transformers() {
+        let description =
+            PipelineDescription::from_toml("input = \"number\"\n[aggregator]\nstage = \"sum\"\n")
+                .unwrap();
+        let error = description.build(&Registry::new()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid pipeline: a pipeline needs at least one transformer"
+        );
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let error =
+            PipelineDescription::from_toml(&format!("threads = 4\n{EVEN_SUM}")).unwrap_err();
+        assert!(matches!(error, PipelineError::Toml(_)));
+    }
+}
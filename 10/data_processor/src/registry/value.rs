@@ -0,0 +1,230 @@
+use crate::datasource::{LinesSource, Source};
+use crate::errors::PipelineError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// An item of a described pipeline
+///
+/// Every stage only gets the kind of values it was registered for, see `ValueType`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Number(u32),
+    Text(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        match self {
+            Value::Number(_) => Kind::Number,
+            Value::Text(_) => Kind::Text,
+            Value::List(_) => Kind::List,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Number,
+    Text,
+    List,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Number => write!(f, "number"),
+            Kind::Text => write!(f, "text"),
+            Kind::List => write!(f, "list"),
+        }
+    }
+}
+
+/// The item types of the typed stages, converted from and to `Value`
+pub trait ValueType: Sized {
+    /// Kind of the values this type stands for, `None` for any kind
+    const KIND: Option<Kind>;
+
+    fn from_value_ref(value: &Value) -> Option<&Self>;
+
+    fn from_value(value: Value) -> Option<Self>;
+
+    fn into_value(self) -> Value;
+}
+
+impl ValueType for Value {
+    const KIND: Option<Kind> = None;
+
+    fn from_value_ref(value: &Value) -> Option<&Self> {
+        Some(value)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value)
+    }
+
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl ValueType for u32 {
+    const KIND: Option<Kind> = Some(Kind::Number);
+
+    fn from_value_ref(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl ValueType for String {
+    const KIND: Option<Kind> = Some(Kind::Text);
+
+    fn from_value_ref(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Text(self)
+    }
+}
+
+impl ValueType for Vec<Value> {
+    const KIND: Option<Kind> = Some(Kind::List);
+
+    fn from_value_ref(value: &Value) -> Option<&Self> {
+        match value {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
+
+/// Every line of the input is a value of `kind`, numbers are parsed
+pub struct ValueLines<R> {
+    lines: LinesSource<R>,
+    kind: Kind,
+}
+
+impl<R: BufRead> ValueLines<R> {
+    pub fn new(reader: R, kind: Kind) -> Self {
+        Self {
+            lines: LinesSource::new(reader),
+            kind,
+        }
+    }
+}
+
+impl ValueLines<BufReader<File>> {
+    /// # Errors
+    ///
+    /// Returns `PipelineError::Io` if the file cannot be opened
+    pub fn open<P: AsRef<Path>>(path: P, kind: Kind) -> Result<Self, PipelineError> {
+        Ok(Self {
+            lines: LinesSource::open(path)?,
+            kind,
+        })
+    }
+}
+
+impl<R: BufRead> Source for ValueLines<R> {
+    type Value = Value;
+    type Item = Value;
+    type Items = ValueLineItems<R>;
+
+    fn into_items(self) -> Self::Items {
+        ValueLineItems {
+            lines: self.lines.into_items(),
+            kind: self.kind,
+            line_number: 0,
+        }
+    }
+}
+
+pub struct ValueLineItems<R: BufRead> {
+    lines: <LinesSource<R> as Source>::Items,
+    kind: Kind,
+    line_number: usize,
+}
+
+impl<R: BufRead> Iterator for ValueLineItems<R> {
+    type Item = Result<Value, PipelineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        self.line_number += 1;
+        Some(line.and_then(|line| match self.kind {
+            Kind::Text => Ok(Value::Text(line)),
+            Kind::Number => line.trim().parse().map(Value::Number).map_err(|e| {
+                PipelineError::SourceError(format!(
+                    "Could not parse line {} as a number: {e}",
+                    self.line_number
+                ))
+            }),
+            Kind::List => Err(PipelineError::SourceError(
+                "A line can't be read as a list".to_string(),
+            )),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_value_lines() {
+        let mut items = ValueLines::new("4\n x\n 7 \n".as_bytes(), Kind::Number).into_items();
+        assert_eq!(items.next().unwrap().unwrap(), Value::Number(4));
+        assert!(
+            matches!(items.next(), Some(Err(PipelineError::SourceError(e))) if e.contains("line 2"))
+        );
+        assert_eq!(items.next().unwrap().unwrap(), Value::Number(7));
+    }
+
+    #[test]
+    fn test_serialize_untagged() {
+        let value = Value::List(vec![Value::Number(1), Value::Text("crab".to_string())]);
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#"[1,"crab"]"#);
+    }
+}
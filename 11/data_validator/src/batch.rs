@@ -0,0 +1,342 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    num::NonZeroUsize,
+    path::Path,
+    thread,
+};
+
+use serde::Serialize;
+
+use crate::errors::ValidatorError;
+use crate::processor::DataProcessor;
+
+/// The errors found in a single column of a single row
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RowReport {
+    row: usize,
+    column: String,
+    errors: Vec<String>,
+}
+
+impl RowReport {
+    #[must_use]
+    pub fn new(row: usize, column: &str, errors: Vec<String>) -> Self {
+        Self {
+            row,
+            column: column.to_string(),
+            errors,
+        }
+    }
+
+    /// 1-based number of the data row, the CSV header is not counted
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    #[must_use]
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    #[must_use]
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+/// Outcome of a batch run, only the failing cells are kept
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct BatchReport {
+    rows_checked: usize,
+    failures: Vec<RowReport>,
+}
+
+impl BatchReport {
+    #[must_use]
+    pub fn rows_checked(&self) -> usize {
+        self.rows_checked
+    }
+
+    /// Failures ordered by row, then by the order the columns were configured in
+    #[must_use]
+    pub fn failures(&self) -> &[RowReport] {
+        &self.failures
+    }
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Writes one `row,column,errors` line per failure, multiple errors are joined with `; `
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::Csv` if the writer fails
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), ValidatorError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["row", "column", "errors"])?;
+        for failure in &self.failures {
+            writer.write_record([
+                failure.row.to_string().as_str(),
+                failure.column.as_str(),
+                failure.errors.join("; ").as_str(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the whole report as a pretty printed JSON object
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::Json` if the writer fails
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), ValidatorError> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// Validates many rows at once, every configured column runs through its own processor
+pub struct BatchValidator {
+    columns: Vec<(String, DataProcessor<String>)>,
+    threads: NonZeroUsize,
+    chunk_size: usize,
+}
+
+impl BatchValidator {
+    // Rows are read and validated in chunks, so memory stays bounded for big files
+    const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+    /// Creates a validator for the given `(column name, processor)` pairs,
+    /// using as many threads as the machine offers
+    #[must_use]
+    pub fn new(columns: Vec<(String, DataProcessor<String>)>) -> Self {
+        let threads = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+        Self {
+            columns,
+            threads,
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    #[must_use]
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    #[must_use]
+    pub fn with_chunk_size(mut self, chunk_size: NonZeroUsize) -> Self {
+        self.chunk_size = chunk_size.get();
+        self
+    }
+
+    /// Validates rows given as an iterator, `headers` names the fields of every row
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::MissingColumn` if a configured column is not in `headers`
+    pub fn validate_iter<S, I>(&self, headers: &[S], rows: I) -> Result<BatchReport, ValidatorError>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = Vec<String>>,
+    {
+        self.validate_records(headers, rows.into_iter().map(Ok))
+    }
+
+    /// Validates CSV data, the first record is expected to be the header
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The data is not valid CSV
+    /// - A configured column is not in the header
+    pub fn validate_csv<R: Read>(&self, reader: R) -> Result<BatchReport, ValidatorError> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        let records = reader.into_records().map(|record| {
+            let record = record?;
+            Ok(record.iter().map(str::to_string).collect())
+        });
+
+        self.validate_records(&headers, records)
+    }
+
+    /// Opens `path` and validates it with `validate_csv`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::Io` if the file cannot be opened, otherwise see `validate_csv`
+    pub fn validate_csv_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<BatchReport, ValidatorError> {
+        let file = File::open(path)?;
+        self.validate_csv(file)
+    }
+
+    fn validate_records<S, I>(
+        &self,
+        headers: &[S],
+        records: I,
+    ) -> Result<BatchReport, ValidatorError>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = Result<Vec<String>, ValidatorError>>,
+    {
+        let indices = self.column_indices(headers)?;
+        let mut report = BatchReport::default();
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+
+        for record in records {
+            chunk.push(record?);
+            if chunk.len() == self.chunk_size {
+                self.validate_chunk(&indices, &mut chunk, &mut report);
+            }
+        }
+        if !chunk.is_empty() {
+            self.validate_chunk(&indices, &mut chunk, &mut report);
+        }
+
+        Ok(report)
+    }
+
+    fn column_indices<S: AsRef<str>>(&self, headers: &[S]) -> Result<Vec<usize>, ValidatorError> {
+        self.columns
+            .iter()
+            .map(|(column, _)| {
+                headers
+                    .iter()
+                    .position(|header| header.as_ref() == column)
+                    .ok_or_else(|| ValidatorError::MissingColumn(column.clone()))
+            })
+            .collect()
+    }
+
+    // Splits the chunk between the threads, joining in spawn order keeps the failures sorted by row
+    fn validate_chunk(
+        &self,
+        indices: &[usize],
+        chunk: &mut Vec<Vec<String>>,
+        report: &mut BatchReport,
+    ) {
+        let rows_per_thread = chunk.len().div_ceil(self.threads.get());
+        let first_row = report.rows_checked;
+
+        let failures: Vec<RowReport> = thread::scope(|scope| {
+            let jobs: Vec<_> = chunk
+                .chunks(rows_per_thread)
+                .enumerate()
+                .map(|(i, rows)| {
+                    let first_row = first_row + i * rows_per_thread;
+                    scope.spawn(move || self.validate_rows(indices, first_row, rows))
+                })
+                .collect();
+
+            jobs.into_iter()
+                .flat_map(|job| job.join().expect("Batch worker thread panicked"))
+                .collect()
+        });
+
+        report.rows_checked += chunk.len();
+        report.failures.extend(failures);
+        chunk.clear();
+    }
+
+    fn validate_rows(
+        &self,
+        indices: &[usize],
+        first_row: usize,
+        rows: &[Vec<String>],
+    ) -> Vec<RowReport> {
+        let mut failures = Vec::new();
+        for (offset, record) in rows.iter().enumerate() {
+            let row = first_row + offset + 1;
+            for ((column, processor), &index) in self.columns.iter().zip(indices) {
+                // By reference, another processor configured for the same column still sees the raw value
+                let errors = match record.get(index) {
+                    Some(value) => match processor.process_all_ref(value) {
+                        Ok(_) => continue,
+                        Err(errors) => errors.iter().map(ToString::to_string).collect(),
+                    },
+                    None => vec!["Value is missing".to_string()],
+                };
+                failures.push(RowReport::new(row, column, errors));
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EmailFormatValidator, MinLengthValidator};
+
+    fn sample_report() -> BatchReport {
+        BatchReport {
+            rows_checked: 3,
+            failures: vec![RowReport::new(
+                2,
+                "email",
+                vec!["first".to_string(), "second".to_string()],
+            )],
+        }
+    }
+
+    #[test]
+    fn test_write_csv() -> Result<(), ValidatorError> {
+        let mut output = Vec::new();
+        sample_report().write_csv(&mut output)?;
+        assert_eq!(
+            String::from_utf8_lossy(&output),
+            "row,column,errors\n2,email,first; second\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_json() -> Result<(), ValidatorError> {
+        let mut output = Vec::new();
+        sample_report().write_json(&mut output)?;
+        let json: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(json["rows_checked"], 3);
+        assert_eq!(json["failures"][0]["row"], 2);
+        assert_eq!(json["failures"][0]["column"], "email");
+        assert_eq!(json["failures"][0]["errors"][1], "second");
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_column() {
+        let validator = BatchValidator::new(vec![(
+            "email".to_string(),
+            DataProcessor::new(vec![], vec![]),
+        )]);
+        let result = validator.validate_iter(&["name"], vec![vec!["John".to_string()]]);
+        assert!(matches!(result, Err(ValidatorError::MissingColumn(_))));
+    }
+
+    #[test]
+    fn test_two_processors_on_one_column() -> Result<(), ValidatorError> {
+        let validator = BatchValidator::new(vec![
+            (
+                "email".to_string(),
+                DataProcessor::new(vec![Box::new(MinLengthValidator::new(64))], vec![]),
+            ),
+            (
+                "email".to_string(),
+                DataProcessor::new(vec![Box::new(EmailFormatValidator::new())], vec![]),
+            ),
+        ]);
+        let report =
+            validator.validate_iter(&["email"], vec![vec!["john@example.com".to_string()]])?;
+        assert_eq!(report.rows_checked, 1);
+        assert!(report.failures.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+};
+
+use crate::errors::ValidatorError;
+use crate::schema::Schema;
+
+/// Where the values to validate are read from
+#[derive(Debug, PartialEq)]
+enum Input {
+    Args(Vec<String>),
+    File(String),
+    Stdin,
+}
+
+/// Command line configuration of the `data_validator` binary
+#[derive(Debug)]
+pub struct CliConfig {
+    schema: Schema,
+    input: Input,
+}
+
+impl CliConfig {
+    pub const USAGE: &'static str =
+        "Usage: data_validator <PROCESSOR | --schema FILE> [--file FILE | VALUE...]
+Validates every value with the named processor (email) or a JSON schema file.
+Values are read from the arguments, from FILE or line by line from stdin.
+Valid values are printed sanitized to stdout, invalid ones are reported on stderr.";
+
+    /// Builds the config from the program arguments, the first one is the program name
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - No processor or schema was given, or a flag is missing its value
+    /// - Both a file and values were given
+    /// - The processor is unknown or the schema file is invalid
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Self, ValidatorError> {
+        args.next();
+
+        let mut schema = None;
+        let mut file = None;
+        let mut values = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-s" | "--schema" => {
+                    let Some(path) = args.next() else {
+                        return Err(Self::invalid("--schema expects a file path"));
+                    };
+                    schema = Some(Schema::from_file(path)?);
+                }
+                "-f" | "--file" => {
+                    let Some(path) = args.next() else {
+                        return Err(Self::invalid("--file expects a file path"));
+                    };
+                    file = Some(path);
+                }
+                _ if schema.is_none() => schema = Some(Schema::named(&arg)?),
+                _ => values.push(arg),
+            }
+        }
+
+        let Some(schema) = schema else {
+            return Err(Self::invalid(
+                "Please provide a processor name or a schema file",
+            ));
+        };
+
+        let input = match (file, values.is_empty()) {
+            (Some(_), false) => {
+                return Err(Self::invalid("Values can't be combined with --file"));
+            }
+            (Some(path), true) => Input::File(path),
+            (None, false) => Input::Args(values),
+            (None, true) => Input::Stdin,
+        };
+
+        Ok(Self { schema, input })
+    }
+
+    /// Validates every value, sanitized values go to `out` and diagnostics to `err`
+    /// as `<source>:<line>: "<value>": <errors>`
+    ///
+    /// Returns how many values were invalid
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::Io` if the input cannot be read or the output cannot be written
+    pub fn run<R, W, E>(&self, stdin: R, mut out: W, mut err: E) -> Result<usize, ValidatorError>
+    where
+        R: BufRead,
+        W: Write,
+        E: Write,
+    {
+        let (source, lines): (&str, Box<dyn Iterator<Item = io::Result<String>> + '_>) =
+            match &self.input {
+                Input::Args(values) => ("arg", Box::new(values.iter().cloned().map(Ok))),
+                Input::File(path) => (
+                    path.as_str(),
+                    Box::new(BufReader::new(File::open(path)?).lines()),
+                ),
+                Input::Stdin => ("stdin", Box::new(stdin.lines())),
+            };
+
+        let processor = self.schema.build_processor();
+        let mut invalid = 0;
+
+        for (index, line) in lines.enumerate() {
+            let value = line?;
+            match processor.process_all_ref(&value) {
+                Ok(data) => writeln!(out, "{}", *data)?,
+                Err(errors) => {
+                    invalid += 1;
+                    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                    writeln!(
+                        err,
+                        "{source}:{}: {value:?}: {}",
+                        index + 1,
+                        errors.join("; ")
+                    )?;
+                }
+            }
+        }
+
+        Ok(invalid)
+    }
+
+    fn invalid(message: &str) -> ValidatorError {
+        ValidatorError::InvalidArguments(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_build_values() -> Result<(), ValidatorError> {
+        let config = CliConfig::build(args(&["ignore", "email", "a@b.com", "c@d.com"]))?;
+        assert_eq!(
+            config.input,
+            Input::Args(vec!["a@b.com".to_string(), "c@d.com".to_string()])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_stdin() -> Result<(), ValidatorError> {
+        let config = CliConfig::build(args(&["ignore", "email"]))?;
+        assert_eq!(config.input, Input::Stdin);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_no_processor_fail() {
+        let result = CliConfig::build(args(&["ignore"]));
+        assert!(matches!(result, Err(ValidatorError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn test_build_file_and_values_fail() {
+        let result = CliConfig::build(args(&["ignore", "email", "--file", "x.txt", "a@b.com"]));
+        assert!(matches!(result, Err(ValidatorError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn test_build_missing_schema_file_fail() {
+        let result = CliConfig::build(args(&["ignore", "--schema", "tests/oops.json"]));
+        assert!(matches!(result, Err(ValidatorError::Io(_))));
+    }
+
+    #[test]
+    fn test_run_stdin() -> Result<(), ValidatorError> {
+        let config = CliConfig::build(args(&["ignore", "email"]))?;
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        let invalid = config.run(
+            "  User@Example.com\nnope\nok@ok.org\n".as_bytes(),
+            &mut out,
+            &mut err,
+        )?;
+
+        assert_eq!(invalid, 1);
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "user@example.com\nok@ok.org\n"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&err),
+            "stdin:2: \"nope\": nope is not a valid email\n"
+        );
+        Ok(())
+    }
+}
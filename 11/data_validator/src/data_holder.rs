@@ -1,18 +1,190 @@
-use std::fmt::Debug;
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::ops::Deref;
 
-#[derive(Debug, PartialEq)]
-pub struct ValidatedData<T> // Here I decided to not use lifetimes, as ValidatedData will always be returned from process, it is better to own the data and be complete
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::markers::{Validation, ValidationRules};
+
+// Here I decided to not use lifetimes, as ValidatedData will always be returned from process, it is better to own the data and be complete
+// Only a DataProcessor<_, M> can create one, so holding a ValidatedData<M> means the value passed the rules of M
+pub struct ValidatedData<M: Validation> {
+    value: M::Value,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<M: Validation> ValidatedData<M> {
+    pub(crate) fn new(value: M::Value) -> Self {
+        Self {
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> M::Value {
+        self.value
+    }
+}
+
+impl<M: Validation> Deref for ValidatedData<M> {
+    type Target = M::Value;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<M: Validation> AsRef<M::Value> for ValidatedData<M> {
+    fn as_ref(&self) -> &M::Value {
+        &self.value
+    }
+}
+
+// Implemented by hand, deriving would require the marker itself to be Debug, PartialEq and Clone
+impl<M: Validation> Debug for ValidatedData<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidatedData")
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<M: Validation> PartialEq for ValidatedData<M>
+where
+    M::Value: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<M: Validation> Clone for ValidatedData<M>
+where
+    M::Value: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<M: Validation> Serialize for ValidatedData<M>
 where
-    T: Debug,
+    M::Value: Serialize,
 {
-    value: T,
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
 }
 
-impl<T> ValidatedData<T>
+// Deserialized input is untrusted, so it runs through the marker's processor again
+impl<'de, M: ValidationRules> Deserialize<'de> for ValidatedData<M>
 where
-    T: Debug,
+    M::Value: Deserialize<'de>,
 {
-    pub fn new(value: T) -> Self {
-        Self { value }
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = M::Value::deserialize(deserializer)?;
+        M::processor()
+            .process(value)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// Returned by DataProcessor::process_ref, borrows the input unless a sanitizer changed it
+pub struct ValidatedRef<'a, M: Validation>
+where
+    M::Value: Clone,
+{
+    value: Cow<'a, M::Value>,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<'a, M: Validation> ValidatedRef<'a, M>
+where
+    M::Value: Clone,
+{
+    pub(crate) fn new(value: Cow<'a, M::Value>) -> Self {
+        Self {
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    /// True when the input was already clean and no copy was made
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.value, Cow::Borrowed(_))
+    }
+
+    pub fn into_owned(self) -> ValidatedData<M> {
+        ValidatedData::new(self.value.into_owned())
+    }
+}
+
+impl<M: Validation> Deref for ValidatedRef<'_, M>
+where
+    M::Value: Clone,
+{
+    type Target = M::Value;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<M: Validation> AsRef<M::Value> for ValidatedRef<'_, M>
+where
+    M::Value: Clone,
+{
+    fn as_ref(&self) -> &M::Value {
+        &self.value
+    }
+}
+
+impl<M: Validation> Debug for ValidatedRef<'_, M>
+where
+    M::Value: Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidatedRef")
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::markers::Email;
+
+    fn email(value: &str) -> ValidatedData<Email> {
+        Email::processor().process(value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_accessors() {
+        let data = email(" User@Example.com ");
+        assert_eq!(data.as_ref(), "user@example.com");
+        assert_eq!(data.len(), 16);
+        assert_eq!(data.into_inner(), "user@example.com");
+    }
+
+    #[test]
+    fn test_serialize() -> Result<(), serde_json::Error> {
+        let json = serde_json::to_string(&email("user@example.com"))?;
+        assert_eq!(json, "\"user@example.com\"");
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_sanitizes() -> Result<(), serde_json::Error> {
+        let data: ValidatedData<Email> = serde_json::from_str("\" USER@example.com\"")?;
+        assert_eq!(data, email("user@example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_invalid_fail() {
+        let result: Result<ValidatedData<Email>, _> = serde_json::from_str("\"user\"");
+        assert!(result.is_err());
     }
 }
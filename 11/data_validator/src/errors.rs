@@ -1,14 +1,58 @@
 use regex::Error;
+use std::fmt;
 
 #[allow(unused)]
 #[derive(Debug)]
 pub enum ValidatorError {
     ValidationError(String),
     RegexValidationError(Error),
+    MissingColumn(String),
+    UnknownProcessor(String),
+    InvalidArguments(String),
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
 }
 
+impl fmt::Display for ValidatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidatorError::ValidationError(e) => write!(f, "{e}"),
+            ValidatorError::RegexValidationError(e) => write!(f, "invalid pattern: {e}"),
+            ValidatorError::MissingColumn(column) => {
+                write!(f, "column {column} is missing from the header")
+            }
+            ValidatorError::UnknownProcessor(name) => write!(f, "unknown processor {name}"),
+            ValidatorError::InvalidArguments(e) => write!(f, "invalid arguments: {e}"),
+            ValidatorError::Io(e) => write!(f, "io error: {e}"),
+            ValidatorError::Csv(e) => write!(f, "csv error: {e}"),
+            ValidatorError::Json(e) => write!(f, "json error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ValidatorError {}
+
 impl From<Error> for ValidatorError {
     fn from(value: Error) -> Self {
         ValidatorError::RegexValidationError(value)
     }
 }
+
+impl From<std::io::Error> for ValidatorError {
+    fn from(value: std::io::Error) -> Self {
+        ValidatorError::Io(value)
+    }
+}
+
+impl From<csv::Error> for ValidatorError {
+    fn from(value: csv::Error) -> Self {
+        ValidatorError::Csv(value)
+    }
+}
+
+impl From<serde_json::Error> for ValidatorError {
+    fn from(value: serde_json::Error) -> Self {
+        ValidatorError::Json(value)
+    }
+}
@@ -1,13 +1,24 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(unused)]
 
+mod batch;
+mod cli;
 mod data_holder;
 mod errors;
+mod markers;
 mod processor;
 mod rules;
 mod sanitizer;
+mod schema;
 mod validator;
 
+pub use batch::{BatchReport, BatchValidator, RowReport};
+pub use cli::CliConfig;
+pub use data_holder::{ValidatedData, ValidatedRef};
+pub use errors::ValidatorError;
+pub use markers::{Email, Processed, Validation, ValidationRules};
 pub use processor::DataProcessor;
+pub use rules::{sanitization_rules::SanitizerRule, validation_rules::ValidatorRule};
+pub use schema::Schema;
 pub use validator::{email_validator::EmailFormatValidator, min_validator::MinLengthValidator, positive_number_validator::PositiveNumberValidator};
 pub use sanitizer::{to_lower_case_sanitizer::ToLowerCaseSanitizer, white_space_sanitizer::TrimWhitespaceSanitizer};
\ No newline at end of file
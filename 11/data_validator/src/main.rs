@@ -1,24 +1,23 @@
-use data_validator::DataProcessor;
-use data_validator::EmailFormatValidator;
-use data_validator::MinLengthValidator;
+use data_validator::CliConfig;
+use std::env;
+use std::io;
+use std::process;
 
-use data_validator::ToLowerCaseSanitizer;
-use data_validator::TrimWhitespaceSanitizer;
-
-pub fn create_processor_email_max_len_20() -> DataProcessor<String>{
-    let email_val = Box::new(EmailFormatValidator::new());
-    let min_val = Box::new(MinLengthValidator::new(20));
-
-    let trim_san = Box::new(TrimWhitespaceSanitizer::new());
-    let low_san = Box::new(ToLowerCaseSanitizer::new());
-
-   DataProcessor::new(vec![email_val, min_val], vec![trim_san, low_san])
-}
 fn main() {
-    let p = create_processor_email_max_len_20();
-    match p.process(&mut String::from("example@example.com")) {
-        Ok(_) => println!("All Good"),
-        Err(e) => println!("Error: {e:?}"),
+    let config = CliConfig::build(env::args()).unwrap_or_else(|err| {
+        eprintln!("Couldn't construct config: {err}");
+        eprintln!("{}", CliConfig::USAGE);
+        process::exit(2);
+    });
+
+    // 0 - everything valid, 1 - some values are invalid, 2 - could not run at all
+    match config.run(io::stdin().lock(), io::stdout().lock(), io::stderr().lock()) {
+        Ok(0) => {}
+        Ok(_) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(2);
+        }
     }
 }
 
@@ -43,4 +42,4 @@ I did, look at the code.
 /*
 Trait Design
 I changed the design after testing few possibilities, it compiled, and the code uses as little clone as possible (non)
-*/
\ No newline at end of file
+*/
@@ -0,0 +1,53 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::processor::DataProcessor;
+use crate::schema::Schema;
+
+/// Names the rules a `ValidatedData` passed, so functions can ask for e.g. `ValidatedData<Email>`
+///
+/// Only `DataProcessor::new` (for `Processed`) and `ValidationRules::processor` hand out
+/// processors, so a marker always means its rules ran
+///
+/// ```compile_fail
+/// # use data_validator::{DataProcessor, Email};
+/// let unchecked = DataProcessor::<String>::new(vec![], vec![]).with_marker::<Email>();
+/// ```
+pub trait Validation {
+    type Value: Debug;
+}
+
+/// A marker whose rules are known at compile time, such data can be validated again when deserialized
+pub trait ValidationRules: Validation + Sized {
+    /// The sanitizers and validators the marker stands for
+    fn rules() -> DataProcessor<Self::Value>;
+
+    /// A processor whose output is marked with `Self`
+    #[must_use]
+    fn processor() -> DataProcessor<Self::Value, Self>
+    where
+        Self::Value: Debug,
+    {
+        Self::rules().with_marker()
+    }
+}
+
+/// Default marker, the data passed some `DataProcessor<T>` built at runtime
+pub struct Processed<T: ?Sized>(PhantomData<fn() -> T>);
+
+impl<T: Debug> Validation for Processed<T> {
+    type Value = T;
+}
+
+/// The data passed the built-in `email` processor: trimmed, lowercased and a valid email
+pub struct Email;
+
+impl Validation for Email {
+    type Value = String;
+}
+
+impl ValidationRules for Email {
+    fn rules() -> DataProcessor<String> {
+        Schema::email().build_processor()
+    }
+}
@@ -1,15 +1,21 @@
+use std::borrow::Cow;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
-use crate::data_holder::ValidatedData;
+use crate::data_holder::{ValidatedData, ValidatedRef};
 use crate::errors::ValidatorError;
+use crate::markers::{Processed, Validation};
 use crate::rules::{sanitization_rules::SanitizerRule, validation_rules::ValidatorRule};
 
-pub struct DataProcessor<T: ?Sized> {
+// M tags the ValidatedData this processor hands out, see markers.rs
+pub struct DataProcessor<T, M = Processed<T>> {
     validators: Vec<Box<dyn ValidatorRule<T>>>,
     sanitizers: Vec<Box<dyn SanitizerRule<T>>>,
+    _marker: PhantomData<fn() -> M>,
 }
 
-impl<T: Debug + Default> DataProcessor<T> {
+impl<T: Debug> DataProcessor<T> {
+    #[must_use]
     pub fn new(
         validators: Vec<Box<dyn ValidatorRule<T>>>,
         sanitizers: Vec<Box<dyn SanitizerRule<T>>>,
@@ -17,26 +23,131 @@ impl<T: Debug + Default> DataProcessor<T> {
         Self {
             validators,
             sanitizers,
+            _marker: PhantomData,
         }
     }
+}
 
-    // 0 Cloning!! But of course we moved data twice ;) is that costly? Is that correct?
-    pub fn process(&self, data: &mut T) -> Result<ValidatedData<T>, ValidatorError> {
-        let mut sanitized_data = std::mem::take(data);
-        for sanitizer in &self.sanitizers {
-            sanitized_data = sanitizer.sanitize(&mut sanitized_data);
+impl<T: Debug, M: Validation<Value = T>> DataProcessor<T, M> {
+    // Tags the output with another marker, only `ValidationRules::processor` may do this
+    // so a marker always stands for the rules it was declared with
+    #[must_use]
+    pub(crate) fn with_marker<N: Validation<Value = T>>(self) -> DataProcessor<T, N> {
+        DataProcessor {
+            validators: self.validators,
+            sanitizers: self.sanitizers,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sanitizes `data` and validates the result, stopping at the first failing validator
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::ValidationError` describing the first validator that rejected the data
+    // 0 Cloning!! The data is moved in and moved out again only when a sanitizer changed it
+    pub fn process(&self, data: T) -> Result<ValidatedData<M>, ValidatorError> {
+        let sanitized_data = self.sanitize(data);
+        self.validate(&sanitized_data)?;
+        Ok(ValidatedData::new(sanitized_data))
+    }
+
+    /// Same as `process`, but runs every validator and reports all of their failures
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of each validator that rejected the sanitized data, in validator order
+    pub fn process_all(&self, data: T) -> Result<ValidatedData<M>, Vec<ValidatorError>> {
+        let sanitized_data = self.sanitize(data);
+
+        let errors = self.validate_all(&sanitized_data);
+        if errors.is_empty() {
+            Ok(ValidatedData::new(sanitized_data))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Same as `process`, but leaves `data` untouched
+    ///
+    /// The result borrows `data` unless a sanitizer had to change it, only then a sanitized copy is made
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::ValidationError` describing the first validator that rejected the data
+    pub fn process_ref<'a>(&self, data: &'a T) -> Result<ValidatedRef<'a, M>, ValidatorError>
+    where
+        T: Clone,
+    {
+        let sanitized_data = self.sanitize_ref(data);
+        self.validate(&sanitized_data)?;
+        Ok(ValidatedRef::new(sanitized_data))
+    }
+
+    /// Same as `process_all`, but leaves `data` untouched like `process_ref`
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of each validator that rejected the sanitized data, in validator order
+    pub fn process_all_ref<'a>(
+        &self,
+        data: &'a T,
+    ) -> Result<ValidatedRef<'a, M>, Vec<ValidatorError>>
+    where
+        T: Clone,
+    {
+        let sanitized_data = self.sanitize_ref(data);
+        let errors = self.validate_all(&sanitized_data);
+        if errors.is_empty() {
+            Ok(ValidatedRef::new(sanitized_data))
+        } else {
+            Err(errors)
         }
+    }
 
+    /// Runs the validators on `data` as it is, without sanitizing it first
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::ValidationError` describing the first validator that rejected the data
+    pub fn validate(&self, data: &T) -> Result<(), ValidatorError> {
         for validator in &self.validators {
-            match validator.validate(&sanitized_data) {
-                Ok(()) => continue,
-                Err(e) => {
-                    return Err(ValidatorError::ValidationError(format!(
-                        "Could not validate data {sanitized_data:?}, cause: {e:?}"
-                    )));
-                }
+            if let Err(e) = validator.validate(data) {
+                return Err(ValidatorError::ValidationError(format!(
+                    "Could not validate data {data:?}, cause: {e:?}"
+                )));
             }
         }
-        Ok(ValidatedData::new(sanitized_data))
+        Ok(())
+    }
+
+    fn validate_all(&self, data: &T) -> Vec<ValidatorError> {
+        self.validators
+            .iter()
+            .filter_map(|validator| validator.validate(data).err())
+            .collect()
+    }
+
+    // Copies `data` only when a sanitizer changes it
+    fn sanitize_ref<'a>(&self, data: &'a T) -> Cow<'a, T>
+    where
+        T: Clone,
+    {
+        let mut sanitized_data = Cow::Borrowed(data);
+        for sanitizer in &self.sanitizers {
+            if let Some(changed) = sanitizer.sanitize(&sanitized_data) {
+                sanitized_data = Cow::Owned(changed);
+            }
+        }
+        sanitized_data
+    }
+
+    fn sanitize(&self, mut data: T) -> T {
+        for sanitizer in &self.sanitizers {
+            if let Some(changed) = sanitizer.sanitize(&data) {
+                data = changed;
+            }
+        }
+        data
     }
 }
@@ -1,4 +1,6 @@
 #[allow(unused)]
-pub trait SanitizerRule<T: ?Sized> {
-    fn sanitize(&self, data: &mut T) -> T;
+// Send + Sync so a DataProcessor can be shared between batch worker threads
+pub trait SanitizerRule<T>: Send + Sync {
+    // None means the data is already clean, so callers only pay for a copy when something changed
+    fn sanitize(&self, data: &T) -> Option<T>;
 }
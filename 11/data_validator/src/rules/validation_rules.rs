@@ -1,5 +1,9 @@
 use crate::errors;
 
-pub trait ValidatorRule<T: ?Sized> {
+// Send + Sync so a DataProcessor can be shared between batch worker threads
+pub trait ValidatorRule<T: ?Sized>: Send + Sync {
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::ValidationError` explaining why `data` is invalid
     fn validate(&self, data: &T) -> Result<(), errors::ValidatorError>;
 }
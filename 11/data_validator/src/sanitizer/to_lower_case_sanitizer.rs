@@ -1,17 +1,23 @@
 use crate::rules::sanitization_rules::SanitizerRule;
 
+#[derive(Default)]
 pub struct ToLowerCaseSanitizer;
 
 #[allow(unused)]
 impl ToLowerCaseSanitizer {
+    #[must_use]
     pub fn new() -> Self {
         Self {}
     }
 }
 
 impl SanitizerRule<String> for ToLowerCaseSanitizer {
-    fn sanitize(&self, data: &mut String) -> String {
-        data.to_lowercase()
+    fn sanitize(&self, data: &String) -> Option<String> {
+        if data.chars().all(|c| c.to_lowercase().eq([c])) {
+            None
+        } else {
+            Some(data.to_lowercase())
+        }
     }
 }
 
@@ -22,16 +28,24 @@ mod test {
     #[test]
     fn test_to_lower_case() {
         let san = ToLowerCaseSanitizer::new();
-        let mut s = "HELLO!".to_string();
-        let result = san.sanitize(&mut s);
-        assert_eq!(result, s.to_lowercase());
+        let s = "HELLO!".to_string();
+        let result = san.sanitize(&s);
+        assert_eq!(result, Some(s.to_lowercase()));
+    }
+
+    #[test]
+    fn test_to_lower_case_already_lower() {
+        let san = ToLowerCaseSanitizer::new();
+        let s = "hello!".to_string();
+        let result = san.sanitize(&s);
+        assert_eq!(result, None);
     }
 
     #[test]
     fn test_to_lower_case_empty_string() {
         let san = ToLowerCaseSanitizer::new();
-        let mut s = "".to_string();
-        let result = san.sanitize(&mut s);
-        assert_eq!(result, s.to_lowercase());
+        let s = String::new();
+        let result = san.sanitize(&s);
+        assert_eq!(result, None);
     }
 }
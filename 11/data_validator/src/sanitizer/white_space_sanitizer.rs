@@ -1,17 +1,24 @@
 use crate::rules::sanitization_rules::SanitizerRule;
 
+#[derive(Default)]
 pub struct TrimWhitespaceSanitizer;
 
 #[allow(unused)]
 impl TrimWhitespaceSanitizer {
+    #[must_use]
     pub fn new() -> Self {
         Self {}
     }
 }
 
 impl SanitizerRule<String> for TrimWhitespaceSanitizer {
-    fn sanitize(&self, data: &mut String) -> String {
-        data.trim().trim_end().to_string()
+    fn sanitize(&self, data: &String) -> Option<String> {
+        let trimmed = data.trim();
+        if trimmed.len() == data.len() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
     }
 }
 
@@ -21,25 +28,25 @@ mod test {
 
     #[test]
     fn test_string_with_spaces() {
-        let mut s = " Some String ".to_string();
+        let s = " Some String ".to_string();
         let san = TrimWhitespaceSanitizer::new();
-        let result = san.sanitize(&mut s);
-        assert_eq!(result, "Some String");
+        let result = san.sanitize(&s);
+        assert_eq!(result.as_deref(), Some("Some String"));
     }
 
     #[test]
     fn test_string_without_spaces() {
-        let mut s = "Some String".to_string();
+        let s = "Some String".to_string();
         let san = TrimWhitespaceSanitizer::new();
-        let result = san.sanitize(&mut s);
-        assert_eq!(result, "Some String");
+        let result = san.sanitize(&s);
+        assert_eq!(result, None);
     }
 
     #[test]
     fn test_empty_string() {
-        let mut s = "".to_string();
+        let s = String::new();
         let san = TrimWhitespaceSanitizer::new();
-        let result = san.sanitize(&mut s);
-        assert_eq!(result, "");
+        let result = san.sanitize(&s);
+        assert_eq!(result, None);
     }
 }
@@ -0,0 +1,156 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::errors::ValidatorError;
+use crate::processor::DataProcessor;
+use crate::rules::{sanitization_rules::SanitizerRule, validation_rules::ValidatorRule};
+use crate::{
+    EmailFormatValidator, MinLengthValidator, ToLowerCaseSanitizer, TrimWhitespaceSanitizer,
+};
+
+/// Describes a `DataProcessor<String>` by rule names, so it can be kept in a JSON file:
+///
+/// ```json
+/// {
+///     "sanitizers": ["trim", "lowercase"],
+///     "validators": [{ "rule": "email" }, { "rule": "min_length", "length": 20 }]
+/// }
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    #[serde(default)]
+    sanitizers: Vec<SanitizerKind>,
+    #[serde(default)]
+    validators: Vec<ValidatorKind>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SanitizerKind {
+    Trim,
+    Lowercase,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+enum ValidatorKind {
+    Email,
+    // Same semantics as MinLengthValidator: non empty and at most `length` bytes
+    MinLength { length: usize },
+}
+
+impl Schema {
+    const PROCESSOR_NAMES: [&'static str; 1] = ["email"];
+
+    /// Returns the schema of a built-in processor
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::UnknownProcessor` if there is no processor called `name`
+    pub fn named(name: &str) -> Result<Self, ValidatorError> {
+        match name {
+            "email" => Ok(Self::email()),
+            _ => Err(ValidatorError::UnknownProcessor(format!(
+                "{name}, available processors: {}",
+                Self::PROCESSOR_NAMES.join(", ")
+            ))),
+        }
+    }
+
+    pub(crate) fn email() -> Self {
+        Self {
+            sanitizers: vec![SanitizerKind::Trim, SanitizerKind::Lowercase],
+            validators: vec![ValidatorKind::Email],
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ValidatorError::Json` if `json` is not a valid schema
+    pub fn from_json(json: &str) -> Result<Self, ValidatorError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The file cannot be read
+    /// - The file is not a valid schema
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ValidatorError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    #[must_use]
+    pub fn build_processor(&self) -> DataProcessor<String> {
+        let validators = self
+            .validators
+            .iter()
+            .map(|kind| -> Box<dyn ValidatorRule<String>> {
+                match kind {
+                    ValidatorKind::Email => Box::new(EmailFormatValidator::new()),
+                    ValidatorKind::MinLength { length } => {
+                        Box::new(MinLengthValidator::new(*length))
+                    }
+                }
+            })
+            .collect();
+
+        let sanitizers = self
+            .sanitizers
+            .iter()
+            .map(|kind| -> Box<dyn SanitizerRule<String>> {
+                match kind {
+                    SanitizerKind::Trim => Box::new(TrimWhitespaceSanitizer::new()),
+                    SanitizerKind::Lowercase => Box::new(ToLowerCaseSanitizer::new()),
+                }
+            })
+            .collect();
+
+        DataProcessor::new(validators, sanitizers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_json() -> Result<(), ValidatorError> {
+        let schema = Schema::from_json(
+            r#"{"sanitizers": ["trim"], "validators": [{"rule": "min_length", "length": 3}]}"#,
+        )?;
+        assert_eq!(
+            schema,
+            Schema {
+                sanitizers: vec![SanitizerKind::Trim],
+                validators: vec![ValidatorKind::MinLength { length: 3 }],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json_unknown_rule() {
+        let result = Schema::from_json(r#"{"validators": [{"rule": "phone"}]}"#);
+        assert!(matches!(result, Err(ValidatorError::Json(_))));
+    }
+
+    #[test]
+    fn test_named_unknown() {
+        let result = Schema::named("phone");
+        assert!(matches!(result, Err(ValidatorError::UnknownProcessor(_))));
+    }
+
+    #[test]
+    fn test_build_processor() {
+        let processor = Schema::named("email").unwrap().build_processor();
+        assert!(
+            processor
+                .process(" User@Example.com ".to_string())
+                .is_ok()
+        );
+        assert!(processor.process("user".to_string()).is_err());
+    }
+}
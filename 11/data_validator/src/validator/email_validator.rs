@@ -1,11 +1,18 @@
 use crate::errors;
 use crate::rules::validation_rules::ValidatorRule;
 use regex::Regex;
+use std::sync::LazyLock;
 
+// Compiled once and shared, batches call validate for every row
+static EMAIL_RGX: LazyLock<Result<Regex, regex::Error>> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$"));
+
+#[derive(Default)]
 pub struct EmailFormatValidator;
 
 #[allow(unused)]
 impl EmailFormatValidator {
+    #[must_use]
     pub fn new() -> Self {
         Self {}
     }
@@ -13,12 +20,12 @@ impl EmailFormatValidator {
 
 impl ValidatorRule<String> for EmailFormatValidator {
     fn validate(&self, data: &String) -> Result<(), errors::ValidatorError> {
-        let email_rgx = Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$")?;
+        let email_rgx = EMAIL_RGX.as_ref().map_err(Clone::clone)?;
         match email_rgx.captures(data) {
             Some(_) => Ok(()),
-            None => Err(errors::ValidatorError::ValidationError(
-                "{data} is not a valid email".to_string(),
-            )),
+            None => Err(errors::ValidatorError::ValidationError(format!(
+                "{data} is not a valid email"
+            ))),
         }
     }
 }
@@ -53,7 +60,7 @@ mod test {
 
     #[test]
     fn test_invalid_email_empty_email() {
-        let invalid_email = "".to_string();
+        let invalid_email = String::new();
         let validator = EmailFormatValidator::new();
         let result = validator.validate(&invalid_email);
         assert!(result.is_err());
@@ -7,6 +7,7 @@ pub struct MinLengthValidator {
 
 #[allow(unused)]
 impl MinLengthValidator {
+    #[must_use]
     pub fn new(length: usize) -> Self {
         Self { length }
     }
@@ -47,7 +48,7 @@ mod test {
     #[test]
     fn test_empty_string() {
         let min_validator = MinLengthValidator::new(10);
-        let test_str = "".to_string();
+        let test_str = String::new();
         let result = min_validator.validate(&test_str);
         assert!(result.is_err());
     }
@@ -55,7 +56,7 @@ mod test {
     #[test]
     fn test_too_big() {
         let min_validator = MinLengthValidator::new(10);
-        let test_str = "a".repeat(11).to_string();
+        let test_str = "a".repeat(11);
         let result = min_validator.validate(&test_str);
         assert!(result.is_err());
     }
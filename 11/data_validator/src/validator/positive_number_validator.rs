@@ -1,10 +1,12 @@
 use crate::errors;
 use crate::rules::validation_rules::ValidatorRule;
 
+#[derive(Default)]
 pub struct PositiveNumberValidator;
 
 #[allow(unused)]
 impl PositiveNumberValidator {
+    #[must_use]
     pub fn new() -> Self {
         Self {}
     }
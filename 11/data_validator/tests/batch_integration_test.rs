@@ -0,0 +1,81 @@
+mod common;
+
+use std::num::NonZeroUsize;
+
+use data_validator::{BatchValidator, DataProcessor, MinLengthValidator};
+
+fn create_batch_validator() -> BatchValidator {
+    let name_processor = DataProcessor::new(vec![Box::new(MinLengthValidator::new(5))], vec![]);
+    BatchValidator::new(vec![
+        (
+            "email".to_string(),
+            common::create_processor_email_max_len_20(),
+        ),
+        ("name".to_string(), name_processor),
+    ])
+}
+
+#[test]
+fn test_batch_csv_all_valid() {
+    let csv = "name,email\nJohn, JOHN@example.com \nJane,jane@example.com\n";
+    let report = create_batch_validator()
+        .validate_csv(csv.as_bytes())
+        .unwrap();
+    assert_eq!(report.rows_checked(), 2);
+    assert!(report.is_valid());
+}
+
+#[test]
+fn test_batch_csv_reports_rows_and_columns() {
+    let csv = "name,email\nJohn,john@example.com\nJonathan,not an email\n,jane@example.com\n";
+    let report = create_batch_validator()
+        .validate_csv(csv.as_bytes())
+        .unwrap();
+
+    let failures: Vec<(usize, &str)> = report
+        .failures()
+        .iter()
+        .map(|failure| (failure.row(), failure.column()))
+        .collect();
+    assert_eq!(failures, vec![(2, "email"), (2, "name"), (3, "name")]);
+    assert_eq!(report.failures()[0].errors().len(), 1);
+}
+
+#[test]
+fn test_batch_csv_missing_value() {
+    let csv = "name,email\nJohn\n";
+    let report = create_batch_validator()
+        .validate_csv(csv.as_bytes())
+        .unwrap();
+    assert_eq!(report.failures().len(), 1);
+    assert_eq!(report.failures()[0].column(), "email");
+}
+
+#[test]
+fn test_batch_iter_matches_single_thread() {
+    let rows: Vec<Vec<String>> = (0..1000)
+        .map(|i| {
+            let email = if i % 7 == 0 {
+                format!("user{i}")
+            } else {
+                format!("user{i}@example.com")
+            };
+            vec![email, "Bob".to_string()]
+        })
+        .collect();
+    let headers = ["email", "name"];
+
+    let parallel = create_batch_validator()
+        .with_threads(NonZeroUsize::new(4).unwrap())
+        .with_chunk_size(NonZeroUsize::new(64).unwrap())
+        .validate_iter(&headers, rows.clone())
+        .unwrap();
+    let sequential = create_batch_validator()
+        .with_threads(NonZeroUsize::MIN)
+        .validate_iter(&headers, rows)
+        .unwrap();
+
+    assert_eq!(parallel, sequential);
+    assert_eq!(parallel.rows_checked(), 1000);
+    assert_eq!(parallel.failures().len(), 143);
+}
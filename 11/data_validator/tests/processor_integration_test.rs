@@ -1,11 +1,15 @@
 mod common;
 
+use data_validator::{
+    DataProcessor, Email, ValidatedData, ValidationRules, ValidatorError, ValidatorRule,
+};
+
 #[test]
 fn test_process_successful() {
     let processor = common::create_processor_email_max_len_20();
-    let mut test_string = "example@example.com".to_string();
+    let test_string = "example@example.com".to_string();
 
-    let result = processor.process(&mut test_string);
+    let result = processor.process(test_string);
     println!("{result:?}");
     assert!(result.is_ok());
 }
@@ -13,9 +17,9 @@ fn test_process_successful() {
 #[test]
 fn test_process_fail_too_long_email() {
     let processor = common::create_processor_email_max_len_20();
-    let mut test_string = "exampasdsadasdasdle@example.com".to_string();
+    let test_string = "exampasdsadasdasdle@example.com".to_string();
 
-    let result = processor.process(&mut test_string);
+    let result = processor.process(test_string);
     println!("{result:?}");
     assert!(result.is_err());
 }
@@ -23,9 +27,9 @@ fn test_process_fail_too_long_email() {
 #[test]
 fn test_process_fail_empty_string() {
     let processor = common::create_processor_email_max_len_20();
-    let mut test_string = "".to_string();
+    let test_string = String::new();
 
-    let result = processor.process(&mut test_string);
+    let result = processor.process(test_string);
     println!("{result:?}");
     assert!(result.is_err());
 }
@@ -33,9 +37,9 @@ fn test_process_fail_empty_string() {
 #[test]
 fn test_process_pass_too_many_spaces_bu_ok_email() {
     let processor = common::create_processor_email_max_len_20();
-    let mut test_string = "                                     asdle@example.com                          ".to_string();
+    let test_string = "                                     asdle@example.com                          ".to_string();
 
-    let result = processor.process(&mut test_string);
+    let result = processor.process(test_string);
     println!("{result:?}");
     assert!(result.is_ok());
 }
@@ -43,9 +47,99 @@ fn test_process_pass_too_many_spaces_bu_ok_email() {
 #[test]
 fn test_process_fail_not_an_email() {
     let processor = common::create_processor_email_max_len_20();
-    let mut test_string = "                                     asdlexample.com                          ".to_string();
+    let test_string = "                                     asdlexample.com                          ".to_string();
+
+    let result = processor.process(test_string);
+    println!("{result:?}");
+    assert!(result.is_err());
+}
+
+fn welcome_message(email: &ValidatedData<Email>) -> String {
+    format!("Welcome {}", email.as_str())
+}
+
+#[test]
+fn test_process_email_marker() {
+    let processor = Email::processor();
+    let test_string = "  Example@Example.com ".to_string();
+
+    let email = processor.process(test_string).unwrap();
+    assert_eq!(welcome_message(&email), "Welcome example@example.com");
+}
+
+#[test]
+fn test_process_ref_borrows_clean_data() {
+    let processor = Email::processor();
+    let test_string = "example@example.com".to_string();
+
+    let email = processor.process_ref(&test_string).unwrap();
+    assert!(email.is_borrowed());
+    assert_eq!(email.as_str(), "example@example.com");
+}
+
+#[test]
+fn test_process_ref_copies_sanitized_data() {
+    let processor = Email::processor();
+    let test_string = "  Example@Example.com ".to_string();
+
+    let email = processor.process_ref(&test_string).unwrap();
+    assert!(!email.is_borrowed());
+    assert_eq!(email.into_owned().into_inner(), "example@example.com");
+    assert_eq!(test_string, "  Example@Example.com ");
+}
 
-    let result = processor.process(&mut test_string);
+#[test]
+fn test_process_ref_fail_not_an_email() {
+    let processor = Email::processor();
+    let test_string = "asdlexample.com".to_string();
+
+    let result = processor.process_ref(&test_string);
     println!("{result:?}");
     assert!(result.is_err());
 }
+
+#[test]
+fn test_process_all_ref_borrows_and_collects() {
+    let processor = Email::processor();
+    let test_string = "example@example.com".to_string();
+
+    let email = processor.process_all_ref(&test_string).unwrap();
+    assert!(email.is_borrowed());
+
+    let not_an_email = "  asdlexample.com ".to_string();
+    let errors = processor.process_all_ref(&not_an_email).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(not_an_email, "  asdlexample.com ");
+}
+
+#[test]
+fn test_validate_does_not_sanitize() {
+    let processor = Email::processor();
+
+    assert!(processor.validate(&"example@example.com".to_string()).is_ok());
+    assert!(processor.validate(&"  example@example.com ".to_string()).is_err());
+}
+
+// No Default, Clone or PartialEq on purpose, process only needs Debug
+#[derive(Debug)]
+struct Port(u16);
+
+struct NonZeroPortValidator;
+
+impl ValidatorRule<Port> for NonZeroPortValidator {
+    fn validate(&self, data: &Port) -> Result<(), ValidatorError> {
+        if data.0 == 0 {
+            Err(ValidatorError::ValidationError("Port 0 is reserved".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_process_type_without_default() {
+    let processor = DataProcessor::new(vec![Box::new(NonZeroPortValidator)], vec![]);
+
+    assert_eq!(processor.process(Port(8080)).unwrap().0, 8080);
+    assert!(processor.process(Port(0)).is_err());
+}
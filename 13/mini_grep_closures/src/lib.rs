@@ -1,41 +1,177 @@
 #![warn(clippy::all, clippy::pedantic)]
 
 pub mod config {
+    use regex::{Regex, RegexBuilder};
     use std::{
         env,
         error::Error,
-        fs::File,
+        fs::{self, File},
         io::{self, BufRead},
         thread,
     };
 
+    /// What part of a line a pattern has to match
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum MatchScope {
+        /// Anywhere in the line
+        #[default]
+        Anywhere,
+        /// Whole words only, `-w`
+        Word,
+        /// The whole line, `-x`
+        Line,
+    }
+
     #[derive(Debug)]
     pub struct Config {
-        query: String,
+        patterns: Vec<String>,
         file_path: String,
         ignore_case: bool,
+        fixed_strings: bool,
+        scope: MatchScope,
+        matcher: Regex,
     }
 
     impl Config {
-        #[must_use]
-        pub fn build(mut args: impl Iterator<Item = String>) -> Result<Self, &'static str> {
+        /// The arguments are `[OPTIONS] PATTERN FILE`, or `[OPTIONS] FILE` with `-e` or `-f`:
+        /// - `-e PATTERN` adds a pattern, can be repeated
+        /// - `-f FILE` adds every line of FILE as a pattern
+        /// - `-F` patterns are fixed strings instead of regular expressions
+        /// - `-w` patterns only match whole words
+        /// - `-x` patterns only match whole lines
+        ///
+        /// A line matches if any of the patterns matches it
+        ///
+        /// # Errors
+        ///
+        /// This function will return error if:
+        /// - The query argument was not provided
+        /// - The `file_path` argument was not provided
+        /// - An option is unknown or misses its value
+        /// - The patterns file cannot be read
+        /// - A pattern is not a valid regular expression
+        pub fn build(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
             args.next();
-            let Some(query) = args.next() else {
-                return Err("Please provide query string!");
-            };
+            let mut patterns = Vec::new();
+            let mut patterns_given = false;
+            let mut fixed_strings = false;
+            let mut scope = MatchScope::Anywhere;
+            let mut operands = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-e" => {
+                        let Some(pattern) = args.next() else {
+                            return Err("Please provide a pattern after -e!".to_string());
+                        };
+                        patterns_given = true;
+                        patterns.push(pattern);
+                    }
+                    "-f" => {
+                        let Some(path) = args.next() else {
+                            return Err("Please provide a patterns file after -f!".to_string());
+                        };
+                        let contents = fs::read_to_string(&path)
+                            .map_err(|e| format!("Could not read patterns file {path}: {e}"))?;
+                        patterns_given = true;
+                        patterns.extend(contents.lines().map(str::to_string));
+                    }
+                    "-F" => fixed_strings = true,
+                    "-w" if scope == MatchScope::Anywhere => scope = MatchScope::Word,
+                    "-w" => {}
+                    "-x" => scope = MatchScope::Line,
+                    option if option.len() > 1 && option.starts_with('-') => {
+                        return Err(format!("Unknown option {option}!"));
+                    }
+                    _ => operands.push(arg),
+                }
+            }
+
+            let mut operands = operands.into_iter();
+            if !patterns_given {
+                let Some(query) = operands.next() else {
+                    return Err("Please provide query string!".to_string());
+                };
+                patterns.push(query);
+            }
 
-            let Some(file_path) = args.next() else {
-                return Err("Please provide file path!");
+            let Some(file_path) = operands.next() else {
+                return Err("Please provide file path!".to_string());
             };
 
             let ignore_case = env::var("IGNORE_CASE").is_ok();
+            let matcher = Self::matcher(&patterns, fixed_strings, scope, ignore_case)
+                .map_err(|e| format!("Invalid pattern: {e}"))?;
             Ok(Self {
-                query,
+                patterns,
                 file_path,
                 ignore_case,
+                fixed_strings,
+                scope,
+                matcher,
             })
         }
 
+        // One regular expression that matches if any of the patterns does
+        fn matcher(
+            patterns: &[String],
+            fixed_strings: bool,
+            scope: MatchScope,
+            ignore_case: bool,
+        ) -> Result<Regex, regex::Error> {
+            // Like grep an empty patterns file matches nothing
+            if patterns.is_empty() {
+                return Regex::new(r"[^\s\S]");
+            }
+            let alternatives: Vec<String> = patterns
+                .iter()
+                .map(|pattern| {
+                    if fixed_strings {
+                        regex::escape(pattern)
+                    } else {
+                        format!("(?:{pattern})")
+                    }
+                })
+                .collect();
+            let any = alternatives.join("|");
+            let pattern = match scope {
+                MatchScope::Anywhere => any,
+                MatchScope::Word => format!(r"\b{{start-half}}(?:{any})\b{{end-half}}"),
+                MatchScope::Line => format!("^(?:{any})$"),
+            };
+            RegexBuilder::new(&pattern)
+                .case_insensitive(ignore_case)
+                .build()
+        }
+
+        /// The patterns of `-e`, `-f` or the query, in that order
+        #[must_use]
+        pub fn patterns(&self) -> &[String] {
+            &self.patterns
+        }
+
+        /// Whether matching ignores case, set by the global env `IGNORE_CASE`
+        #[must_use]
+        pub fn ignore_case(&self) -> bool {
+            self.ignore_case
+        }
+
+        /// Whether the patterns are fixed strings, `-F`
+        #[must_use]
+        pub fn fixed_strings(&self) -> bool {
+            self.fixed_strings
+        }
+
+        #[must_use]
+        pub fn scope(&self) -> MatchScope {
+            self.scope
+        }
+
+        /// Whether any of the patterns matches `line`
+        #[must_use]
+        pub fn is_match(&self, line: &str) -> bool {
+            self.matcher.is_match(line)
+        }
+
         /// Does something with a file
         ///
         /// # Errors
@@ -44,19 +180,21 @@ pub mod config {
         /// - The file cannot be opened
         /// - The file cannot be read
         /// - The file contains invalid data
+        ///
+        /// # Panics
+        ///
+        /// Panics if a search thread panics
         pub fn parse_config(&self) -> Result<(), Box<dyn Error>> {
             let file_path = format!("./{}.txt", self.file_path);
             let file = File::open(file_path)?;
             let buf_reader = io::BufReader::new(file);
 
-            let ignore_case = self.ignore_case;
-
-            let jobs: Vec<_> = buf_reader
-                .lines()
-                .filter_map(std::result::Result::ok)
+            let lines = buf_reader.lines().collect::<Result<Vec<_>, _>>()?;
+            let jobs: Vec<_> = lines
+                .into_iter()
                 .map(|data| {
-                    let query = self.query.to_string();
-                    thread::spawn(move || Config::search(query, data, ignore_case))
+                    let matcher = self.matcher.clone();
+                    thread::spawn(move || Config::search(&matcher, &data))
                 })
                 .collect();
 
@@ -67,15 +205,9 @@ pub mod config {
             Ok(())
         }
 
-        fn search(query: String, data: String, ignore_case: bool) {
-            if ignore_case {
-                if data.to_lowercase().contains(query.to_lowercase().as_str()) {
-                    println!("{data}");
-                }
-            } else {
-                if data.contains(&query) {
-                    println!("{data}");
-                }
+        fn search(matcher: &Regex, data: &str) {
+            if matcher.is_match(data) {
+                println!("{data}");
             }
         }
     }
@@ -84,6 +216,84 @@ pub mod config {
     mod test {
         use super::*;
 
+        fn build(args: &[&str]) -> Result<Config, String> {
+            Config::build(args.iter().map(ToString::to_string))
+        }
+
+        #[test]
+        fn build_regex_pass() -> Result<(), String> {
+            let config = build(&["ignore", "^Some R.*t$", "tests/some_text"])?;
+            assert!(config.is_match("Some Random text"));
+            assert!(!config.is_match("This is some"));
+            Ok(())
+        }
+
+        #[test]
+        fn build_fixed_strings_pass() -> Result<(), String> {
+            let config = build(&["ignore", "-F", "R.st!", "tests/some_text"])?;
+            assert!(config.fixed_strings());
+            assert!(config.is_match("R.st! :)"));
+            assert!(!config.is_match("Rust! :)"));
+            Ok(())
+        }
+
+        #[test]
+        fn build_whole_word_and_line_pass() -> Result<(), String> {
+            let config = build(&["ignore", "-w", "test", "tests/some_text"])?;
+            assert_eq!(config.scope(), MatchScope::Word);
+            assert!(config.is_match("To test the test"));
+            assert!(!config.is_match("testing"));
+
+            let config = build(&["ignore", "-w", "-F", "c++", "tests/some_text"])?;
+            assert!(config.is_match("use c++ now"));
+            assert!(config.is_match("c++"));
+            assert!(!config.is_match("use c++x now"));
+
+            let config = build(&["ignore", "-w", "-x", "Just to test", "tests/some_text"])?;
+            assert_eq!(config.scope(), MatchScope::Line);
+            assert!(config.is_match("Just to test"));
+            assert!(!config.is_match("Just to test it"));
+            Ok(())
+        }
+
+        #[test]
+        fn build_multiple_patterns_pass() -> Result<(), Box<dyn Error>> {
+            let args = [
+                "ignore",
+                "-e",
+                "Rust",
+                "-f",
+                "tests/patterns.txt",
+                "-e",
+                "Random",
+                "tests/some_text",
+            ];
+            let config = build(&args)?;
+            assert_eq!(config.patterns(), ["Rust", "^Just", "the test$", "Random"]);
+            assert!(config.is_match("Just to test"));
+            assert!(config.is_match("To test the test"));
+            assert!(!config.is_match("This is some"));
+            config.parse_config()
+        }
+
+        #[test]
+        fn build_empty_patterns_file_pass() -> Result<(), Box<dyn Error>> {
+            let config = build(&["ignore", "-f", "tests/no_patterns.txt", "tests/some_text"])?;
+            assert!(config.patterns().is_empty());
+            assert!(!config.is_match("Just to test"));
+            assert!(!config.is_match(""));
+            config.parse_config()
+        }
+
+        #[test]
+        fn build_bad_patterns_fail() {
+            assert!(build(&["ignore", "(", "tests/some_text"]).is_err());
+            assert!(build(&["ignore", "tests/some_text", "-e"]).is_err());
+            assert!(build(&["ignore", "-f", "tests/oops.txt", "tests/some_text"]).is_err());
+            assert!(build(&["ignore", "-q", "rust", "tests/some_text"]).is_err());
+            assert!(build(&["ignore", "-e", "rust"]).is_err());
+        }
+
         #[test]
         fn parse_config_pass() -> Result<(), Box<dyn Error>> {
             let args = vec![
@@ -136,6 +346,13 @@ pub mod config {
             }
         }
 
+        #[test]
+        fn parse_config_not_utf8_fail() -> Result<(), String> {
+            let config = build(&["ignore", "rust", "tests/not_utf8"])?;
+            assert!(config.parse_config().is_err());
+            Ok(())
+        }
+
         #[test]
         fn parse_config_no_file_fail() {
             let args = vec![
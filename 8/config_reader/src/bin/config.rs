@@ -0,0 +1,16 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+use config_reader::cli::Cli;
+use std::{env, io, process};
+
+// `config get max_retries`, see `cli::USAGE` for the commands and `cli::EXIT_*` for the exit codes
+fn main() {
+    let code = match Cli::parse(env::args().skip(1)).and_then(|cli| cli.run(&mut io::stdout())) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{e}");
+            e.exit_code()
+        }
+    };
+    process::exit(code);
+}
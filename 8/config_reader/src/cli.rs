@@ -0,0 +1,573 @@
+use crate::{
+    config::Config,
+    custom_error::ConfigError,
+    include,
+    reader::ConfigReader,
+    schema::Schema,
+    secret::{self, Secret},
+    watch::{Change, Diff},
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// `diff` found settings that differ
+pub const EXIT_DIFFERENT: i32 = 1;
+/// The arguments are not a command
+pub const EXIT_USAGE: i32 = 2;
+/// `get` or `unset` of a key the config does not have
+pub const EXIT_MISSING_KEY: i32 = 3;
+/// A config or schema file is not valid, or a setting does not fit the schema
+pub const EXIT_INVALID: i32 = 4;
+/// A file can't be found, read or written
+pub const EXIT_IO: i32 = 5;
+
+/// The prefix of the variables `dump --format env` prints, the one `Layers::env` reads
+pub const ENV_PREFIX: &str = "APP";
+
+pub const USAGE: &str = "\
+usage: config [--config FILE] [--schema FILE] [--show-secrets] COMMAND
+
+commands:
+  get KEY                       print the value of KEY
+  set KEY VALUE                 change KEY to VALUE in the config file
+  unset KEY                     remove KEY from the config file
+  validate --schema FILE        check the config against a schema file
+  dump [--format json|toml|env] print every setting, as json by default
+  diff FILE_A FILE_B            print the settings that differ between two configs
+
+Without --config the config is found like Config::discover does.
+get, dump and diff print secrets as [REDACTED], --schema tells which settings are secrets
+and --show-secrets prints their plaintext";
+
+/// A command of the `config` binary, see `USAGE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Get(String),
+    Set(String, String),
+    Unset(String),
+    Validate { schema: PathBuf },
+    Dump(DumpFormat),
+    Diff(PathBuf, PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Json,
+    Toml,
+    /// `APP_SERVER__PORT='8080'` lines, what `Layers::env` reads back
+    Env,
+}
+
+/// Why a command failed, each kind has its own exit code
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    MissingKey(String),
+    Config(ConfigError),
+}
+
+impl CliError {
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::MissingKey(_) => EXIT_MISSING_KEY,
+            CliError::Config(ConfigError::Io(_) | ConfigError::NotFound { .. }) => EXIT_IO,
+            CliError::Config(_) => EXIT_INVALID,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{message}\n\n{USAGE}"),
+            CliError::MissingKey(key) => write!(f, "{key} is not set"),
+            CliError::Config(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<ConfigError> for CliError {
+    fn from(error: ConfigError) -> Self {
+        CliError::Config(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Config(ConfigError::Io(error))
+    }
+}
+
+/// The parsed arguments of the `config` binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    config: Option<String>,
+    // Checks the config and declares its secrets for every command
+    schema: Option<PathBuf>,
+    show_secrets: bool,
+    command: Command,
+}
+
+impl Cli {
+    /// Parses the arguments without the name of the program
+    ///
+    /// # Errors
+    ///
+    /// Returns `CliError::Usage` if the arguments are not a command
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut args = args.into_iter();
+        let usage = |message: &str| CliError::Usage(message.to_string());
+
+        let mut config = None;
+        let mut schema = None;
+        let mut show_secrets = false;
+        let name = loop {
+            match args.next().as_deref() {
+                Some("--config") => {
+                    config = Some(args.next().ok_or_else(|| usage("--config needs a file"))?);
+                }
+                Some("--schema") => {
+                    let path = args.next().ok_or_else(|| usage("--schema needs a file"))?;
+                    schema = Some(path.into());
+                }
+                Some("--show-secrets") => show_secrets = true,
+                Some(name) => break name.to_string(),
+                None => return Err(usage("Please provide a command")),
+            }
+        };
+        let next = |args: &mut dyn Iterator<Item = String>, what: &str| {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("{name} needs {what}")))
+        };
+
+        let command = match name.as_str() {
+            "help" | "--help" | "-h" => Command::Help,
+            "get" => Command::Get(next(&mut args, "a key")?),
+            "set" => Command::Set(next(&mut args, "a key")?, next(&mut args, "a value")?),
+            "unset" => Command::Unset(next(&mut args, "a key")?),
+            "validate" => match next(&mut args, "--schema FILE")?.as_str() {
+                "--schema" => Command::Validate {
+                    schema: next(&mut args, "a schema file")?.into(),
+                },
+                other => return Err(CliError::Usage(format!("{other} is not --schema"))),
+            },
+            "dump" => match args.next().as_deref() {
+                None => Command::Dump(DumpFormat::Json),
+                Some("--format") => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| usage("--format needs json, toml or env"))?;
+                    Command::Dump(match format.as_str() {
+                        "json" => DumpFormat::Json,
+                        "toml" => DumpFormat::Toml,
+                        "env" => DumpFormat::Env,
+                        other => {
+                            return Err(CliError::Usage(format!(
+                                "{other} is not json, toml or env"
+                            )));
+                        }
+                    })
+                }
+                Some(other) => return Err(CliError::Usage(format!("{other} is not --format"))),
+            },
+            "diff" => Command::Diff(
+                next(&mut args, "two files")?.into(),
+                next(&mut args, "two files")?.into(),
+            ),
+            other => return Err(CliError::Usage(format!("{other} is not a command"))),
+        };
+        if let Some(extra) = args.next() {
+            return Err(CliError::Usage(format!("{extra} is one argument too many")));
+        }
+        Ok(Self {
+            config,
+            schema,
+            show_secrets,
+            command,
+        })
+    }
+
+    #[must_use]
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Runs the command and writes what it prints to `out`, returns the exit code
+    ///
+    /// # Errors
+    ///
+    /// Returns `CliError::MissingKey` if `get` or `unset` can't find the key,
+    /// or `CliError::Config` if a file can't be read, written or is not valid,
+    /// or if `unset` gets a key that is not in the config file itself
+    pub fn run(&self, out: &mut impl io::Write) -> Result<i32, CliError> {
+        match &self.command {
+            Command::Help => writeln!(out, "{USAGE}")?,
+            Command::Get(key) => {
+                let reader = self.load(self.schema()?)?;
+                let redacted = reader.redacted_values();
+                let value = redacted
+                    .get(key.as_str())
+                    .ok_or_else(|| CliError::MissingKey(key.clone()))?;
+                writeln!(out, "{}", self.shown(&reader, key, value)?)?;
+            }
+            Command::Set(key, value) => {
+                let mut reader = self.load(self.schema()?)?;
+                reader.set(key, value)?;
+                reader.save()?;
+            }
+            Command::Unset(key) => {
+                let mut reader = self.load(self.schema()?)?;
+                if reader.remove(key)?.is_none() {
+                    return Err(CliError::MissingKey(key.clone()));
+                }
+                reader.save()?;
+            }
+            Command::Validate { schema } => {
+                let reader = self.load(Some(Schema::from_file(schema)?))?;
+                writeln!(out, "{} is valid", reader.config_path())?;
+            }
+            Command::Dump(format) => {
+                let reader = self.load(self.schema()?)?;
+                let values = reader
+                    .redacted_values()
+                    .into_iter()
+                    .map(|(key, value)| Ok((key.to_string(), self.shown(&reader, key, value)?)))
+                    .collect::<Result<HashMap<_, _>, ConfigError>>()?;
+                write!(out, "{}", dump(&values, *format)?)?;
+            }
+            Command::Diff(a, b) => {
+                let schema = self.schema()?;
+                let diff = Diff::between(&read_settings(a)?, &read_settings(b)?);
+                for (key, change) in diff.iter() {
+                    let hidden = !self.show_secrets && is_secret(schema.as_ref(), key, change);
+                    let shown = |value| if hidden { secret::REDACTED } else { value };
+                    match change {
+                        Change::Added(new) => writeln!(out, "+ {key} = {}", shown(new))?,
+                        Change::Removed(old) => writeln!(out, "- {key} = {}", shown(old))?,
+                        Change::Changed { .. } if hidden => writeln!(out, "~ {key} changed")?,
+                        Change::Changed { old, new } => writeln!(out, "~ {key} = {old} -> {new}")?,
+                    }
+                }
+                if !diff.is_empty() {
+                    return Ok(EXIT_DIFFERENT);
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    fn schema(&self) -> Result<Option<Schema>, ConfigError> {
+        self.schema.as_deref().map(Schema::from_file).transpose()
+    }
+
+    // Secrets stay redacted unless `--show-secrets` asks for their plaintext
+    fn shown(
+        &self,
+        reader: &ConfigReader,
+        key: &str,
+        redacted: &str,
+    ) -> Result<String, ConfigError> {
+        if self.show_secrets && reader.is_secret(key) {
+            Ok(reader.get_secret(key)?.expose().to_string())
+        } else {
+            Ok(redacted.to_string())
+        }
+    }
+
+    // Every problem the schema finds is reported, not only the first one
+    fn load(&self, schema: Option<Schema>) -> Result<ConfigReader, ConfigError> {
+        let config = Config::discover(self.config.as_deref())?;
+        let mut reader = ConfigReader::new(Some(&config))?;
+        if let Some(schema) = schema {
+            reader = reader.with_schema(schema).collect_errors();
+        }
+        reader.load_config()?;
+        Ok(reader)
+    }
+}
+
+// The settings of a file and the files it includes
+fn read_settings(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    Ok(include::read_file(path, None, false)?
+        .into_iter()
+        .map(|(key, value, _)| (key, value))
+        .collect())
+}
+
+// Like `ConfigReader::is_secret`, the schema declares `key` a secret or a value refers to one
+fn is_secret(schema: Option<&Schema>, key: &str, change: &Change) -> bool {
+    let values = match change {
+        Change::Added(value) | Change::Removed(value) => [Some(value), None],
+        Change::Changed { old, new } => [Some(old), Some(new)],
+    };
+    values
+        .into_iter()
+        .flatten()
+        .any(|value| Secret::is_reference(value))
+        || schema.is_some_and(|schema| schema.is_secret(key))
+}
+
+/// Prints `values` in `format`, dotted keys are nested tables in json and toml
+///
+/// # Errors
+///
+/// Returns `ConfigError::InvalidFormat` if a key is both a setting and a table,
+/// e.g. `server` and `server.port`, json and toml can't have both
+pub fn dump<S: std::hash::BuildHasher>(
+    values: &HashMap<String, String, S>,
+    format: DumpFormat,
+) -> Result<String, ConfigError> {
+    let sorted: BTreeMap<&String, &String> = values.iter().collect();
+    if format == DumpFormat::Env {
+        let lines: Vec<String> = sorted
+            .into_iter()
+            .map(|(key, value)| {
+                let var = key.to_ascii_uppercase().replace('.', "__");
+                format!("{ENV_PREFIX}_{var}={}\n", shell_quote(value))
+            })
+            .collect();
+        return Ok(lines.concat());
+    }
+
+    let mut root = serde_json::Map::new();
+    for (key, value) in sorted {
+        let mut table = &mut root;
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let name = parts.pop().unwrap_or_default();
+        for part in parts {
+            table = match table
+                .entry(part)
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+            {
+                serde_json::Value::Object(table) => table,
+                _ => return Err(conflict(key)),
+            };
+        }
+        if table.contains_key(name) {
+            return Err(conflict(key));
+        }
+        table.insert(name.to_string(), serde_json::Value::String(value.clone()));
+    }
+
+    let root = serde_json::Value::Object(root);
+    let text = match format {
+        DumpFormat::Toml => toml::to_string(&root)
+            .map_err(|e| ConfigError::InvalidFormat(format!("Can't write toml: {e}")))?,
+        _ => serde_json::to_string_pretty(&root)
+            .map_err(|e| ConfigError::InvalidFormat(format!("Can't write json: {e}")))?,
+    };
+    Ok(format!("{}\n", text.trim_end()))
+}
+
+fn conflict(key: &str) -> ConfigError {
+    ConfigError::InvalidFormat(format!("{key} is in a setting that is also a table"))
+}
+
+// Bare if the shell leaves the value alone, in single quotes otherwise
+fn shell_quote(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:@,+%".contains(c));
+    if bare {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    fn cli(args: &[&str]) -> Result<Cli, CliError> {
+        Cli::parse(args.iter().map(ToString::to_string))
+    }
+
+    fn run(args: &[&str]) -> (Result<i32, CliError>, String) {
+        let mut out = Vec::new();
+        let result = cli(args).unwrap().run(&mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = cli(&["--config", "app.ini", "set", "port", "80"]).unwrap();
+        assert_eq!(parsed.config.as_deref(), Some("app.ini"));
+        assert_eq!(*parsed.command(), Command::Set("port".into(), "80".into()));
+        assert_eq!(
+            *cli(&["dump", "--format", "env"]).unwrap().command(),
+            Command::Dump(DumpFormat::Env)
+        );
+
+        for args in [
+            &[][..],
+            &["get"],
+            &["dump", "--format", "xml"],
+            &["validate", "schema.ini"],
+            &["get", "a", "b"],
+            &["remove", "a"],
+        ] {
+            let error = cli(args).unwrap_err();
+            assert_eq!(error.exit_code(), EXIT_USAGE, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_get_set_unset() {
+        let path = std::env::temp_dir().join(format!("cli_{}.ini", std::process::id()));
+        fs::write(&path, "; app\nname = app\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(run(&["--config", path, "set", "port", "80"]).0.unwrap(), 0);
+        let (result, out) = run(&["--config", path, "get", "port"]);
+        assert_eq!((result.unwrap(), out.as_str()), (0, "80\n"));
+        run(&["--config", path, "unset", "name"]).0.unwrap();
+
+        let missing = run(&["--config", path, "get", "name"]).0.unwrap_err();
+        let unset = run(&["--config", path, "unset", "name"]).0.unwrap_err();
+        let saved = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(saved, "; app\nport = 80\n");
+        assert_eq!(missing.exit_code(), EXIT_MISSING_KEY);
+        assert_eq!(unset.exit_code(), EXIT_MISSING_KEY);
+        let error = run(&["--config", path, "get", "port"]).0.unwrap_err();
+        assert_eq!(error.exit_code(), EXIT_IO);
+    }
+
+    #[test]
+    fn test_validate_and_diff() {
+        let dir = std::env::temp_dir().join(format!("cli_validate_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.ini"), "max_retries = 50\nport = 80\n").unwrap();
+        fs::write(dir.join("b.toml"), "max_retries = 5\nhost = \"db\"\n").unwrap();
+        fs::write(dir.join("schema.ini"), "max_retries = uint min=1 max=10\n").unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        let invalid = run(&[
+            "--config",
+            &file("a.ini"),
+            "validate",
+            "--schema",
+            &file("schema.ini"),
+        ]);
+        let valid = run(&[
+            "--config",
+            &file("b.toml"),
+            "validate",
+            "--schema",
+            &file("schema.ini"),
+        ]);
+        let diff = run(&["diff", &file("a.ini"), &file("b.toml")]);
+        let same = run(&["diff", &file("a.ini"), &file("a.ini")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(invalid.0.unwrap_err().exit_code(), EXIT_INVALID);
+        assert_eq!(valid.0.unwrap(), 0);
+        assert_eq!(diff.0.unwrap(), EXIT_DIFFERENT);
+        assert_eq!(
+            diff.1,
+            "+ host = db\n~ max_retries = 50 -> 5\n- port = 80\n"
+        );
+        assert_eq!(same.0.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let dir = std::env::temp_dir().join(format!("cli_secrets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.ini"), "db_password = hunter2\nport = 80\n").unwrap();
+        fs::write(dir.join("schema.ini"), "db_password = secret\n").unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (config, schema) = (file("app.ini"), file("schema.ini"));
+
+        let dump = run(&["--config", &config, "--schema", &schema, "dump"]);
+        let get = run(&[
+            "--config",
+            &config,
+            "--schema",
+            &schema,
+            "get",
+            "db_password",
+        ]);
+        let shown = run(&[
+            "--config",
+            &config,
+            "--schema",
+            &schema,
+            "--show-secrets",
+            "get",
+            "db_password",
+        ]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!dump.1.contains("hunter2") && dump.1.contains("[REDACTED]"));
+        assert_eq!(get.1, "[REDACTED]\n");
+        assert_eq!(shown.1, "hunter2\n");
+    }
+
+    #[test]
+    fn test_diff_redacts_secrets() {
+        let dir = std::env::temp_dir().join(format!("cli_diff_secrets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.ini"), "db_password = hunter2\nport = 80\n").unwrap();
+        fs::write(
+            dir.join("b.ini"),
+            "db_password = s3cr3t\nport = 81\ntoken = @env:TOKEN\n",
+        )
+        .unwrap();
+        fs::write(dir.join("schema.ini"), "db_password = secret\n").unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (a, b, schema) = (file("a.ini"), file("b.ini"), file("schema.ini"));
+
+        let diff = run(&["--schema", &schema, "diff", &a, &b]);
+        let shown = run(&["--schema", &schema, "--show-secrets", "diff", &a, &b]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(diff.0.unwrap(), EXIT_DIFFERENT);
+        assert_eq!(
+            diff.1,
+            "~ db_password changed\n~ port = 80 -> 81\n+ token = [REDACTED]\n"
+        );
+        assert!(shown.1.contains("~ db_password = hunter2 -> s3cr3t\n"));
+    }
+
+    #[test]
+    fn test_dump() {
+        let values: HashMap<String, String> = [
+            ("server.port", "80"),
+            ("server.tls.cert", "a.pem"),
+            ("name", "it's me"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+        assert_eq!(
+            dump(&values, DumpFormat::Env).unwrap(),
+            "APP_NAME='it'\\''s me'\nAPP_SERVER__PORT=80\nAPP_SERVER__TLS__CERT=a.pem\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&dump(&values, DumpFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["server"]["tls"]["cert"], "a.pem");
+        let toml: toml::Table = dump(&values, DumpFormat::Toml).unwrap().parse().unwrap();
+        assert_eq!(toml["server"]["port"].as_str(), Some("80"));
+
+        let mut values = values;
+        values.insert("server".to_string(), "x".to_string());
+        assert!(matches!(
+            dump(&values, DumpFormat::Json),
+            Err(ConfigError::InvalidFormat(_))
+        ));
+    }
+}
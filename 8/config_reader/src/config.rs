@@ -1,6 +1,10 @@
 use crate::custom_error::ConfigError;
 
-use std::{fs, io::ErrorKind, path::Path};
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 
 #[derive(Clone)]
 pub struct Config {
@@ -8,42 +12,176 @@ pub struct Config {
 }
 
 impl Config {
+    #[must_use]
     pub fn get_config_path(&self) -> &str {
         &self.config_path
     }
 }
 
 impl Config {
-    // I looked that up, I have no idea what it is used for and how yet
-    const CONFIG_FILE_NAME: &'static str = "config.txt";
-
-    // This is a function for testing std::fs and getting a feeling of using custom errors
-    fn new_default() -> Result<Self, ConfigError> {
-        let mut files_in_current_folder =
-            fs::read_dir("./src").expect("Could not read from directory");
-
-        if files_in_current_folder.any(|file| {
-            file.as_ref().unwrap().file_name().to_str().unwrap() == Self::CONFIG_FILE_NAME
-        }) {
-            let config_path = format!("./src/{}", Self::CONFIG_FILE_NAME);
-            Ok(Self { config_path })
-        } else {
-            Err(ConfigError::Io(std::io::Error::new(
-                ErrorKind::NotFound,
-                format!("No {} file was found", Self::CONFIG_FILE_NAME),
-            )))
+    /// The directory of the config in the XDG config dirs and `/etc`
+    pub const APP_NAME: &'static str = "config_reader";
+    /// Names the config file to use instead of searching for one,
+    /// `Layers::env` does not read it as the setting `config`
+    pub const PATH_VAR: &'static str = "APP_CONFIG";
+    const FILE_NAMES: [&'static str; 6] = [
+        "config.toml",
+        "config.yaml",
+        "config.yml",
+        "config.json",
+        "config.ini",
+        "config.txt",
+    ];
+
+    /// The config file at `config_path`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::NotFound` if there is no such file
+    pub fn new(config_path: &str) -> Result<Self, ConfigError> {
+        Self::first_existing(vec![PathBuf::from(config_path)])
+    }
+
+    /// Finds the config file in the first of these places that has one:
+    /// 1. `explicit`, if there is one nothing else is searched
+    /// 2. `$APP_CONFIG`, if it is set nothing else is searched
+    /// 3. the current directory
+    /// 4. `$XDG_CONFIG_HOME/config_reader`, by default `~/.config/config_reader`
+    /// 5. `config_reader` in every dir of `$XDG_CONFIG_DIRS`, by default `/etc/xdg`
+    /// 6. `/etc/config_reader`
+    ///
+    /// A directory has a config named `config` with any extension `Format` knows
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::NotFound` with the searched paths if there is no config file
+    pub fn discover(explicit: Option<&str>) -> Result<Self, ConfigError> {
+        Self::first_existing(Self::search_paths(explicit, |var| env::var_os(var)))
+    }
+
+    // `var` looks up environment variables
+    fn search_paths(
+        explicit: Option<&str>,
+        var: impl Fn(&str) -> Option<OsString>,
+    ) -> Vec<PathBuf> {
+        if let Some(explicit) = explicit {
+            return vec![PathBuf::from(explicit)];
         }
+        if let Some(path) = var(Self::PATH_VAR).filter(|path| !path.is_empty()) {
+            return vec![PathBuf::from(path)];
+        }
+
+        // Empty or relative XDG variables are to be ignored
+        let absolute = |dir: PathBuf| dir.is_absolute().then_some(dir);
+        let config_home = var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .and_then(absolute)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".config")));
+        let config_dirs: Vec<PathBuf> = var("XDG_CONFIG_DIRS")
+            .map(|dirs| env::split_paths(&dirs).filter_map(absolute).collect())
+            .filter(|dirs: &Vec<PathBuf>| !dirs.is_empty())
+            .unwrap_or_else(|| vec![PathBuf::from("/etc/xdg")]);
+
+        let mut dirs = vec![PathBuf::new()];
+        dirs.extend(
+            config_home
+                .into_iter()
+                .chain(config_dirs)
+                .chain([PathBuf::from("/etc")])
+                .map(|dir| dir.join(Self::APP_NAME)),
+        );
+        dirs.iter()
+            .flat_map(|dir| Self::FILE_NAMES.map(|name| dir.join(name)))
+            .collect()
     }
 
-    pub fn new(config_path: &str) -> Result<Self, ConfigError> {
-        if Path::new(config_path).exists() {
-            println!("Found {config_path}");
-            Ok(Self {
-                config_path: config_path.to_string(),
-            })
-        } else {
-            println!("Could not read {config_path}, trying to read default");
-            Self::new_default()
+    fn first_existing(searched: Vec<PathBuf>) -> Result<Self, ConfigError> {
+        match searched.iter().find(|path| path.is_file()) {
+            Some(path) => Ok(Self {
+                config_path: path.to_string_lossy().into_owned(),
+            }),
+            None => Err(ConfigError::NotFound { searched }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: Vec<(String, OsString)> = vars
+            .iter()
+            .map(|(var, value)| ((*var).to_string(), OsString::from(value)))
+            .collect();
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone())
         }
     }
+
+    #[test]
+    fn test_explicit_paths_win() {
+        let vars = lookup(&[("APP_CONFIG", "/srv/app.toml")]);
+        assert_eq!(
+            Config::search_paths(Some("mine.yaml"), &vars),
+            [PathBuf::from("mine.yaml")]
+        );
+        assert_eq!(
+            Config::search_paths(None, &vars),
+            [PathBuf::from("/srv/app.toml")]
+        );
+        let layers = crate::layers::Layers::new().env_vars(
+            crate::cli::ENV_PREFIX,
+            [(Config::PATH_VAR.to_string(), "/srv/app.toml".to_string())],
+        );
+        assert!(layers.merge().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_order() {
+        let paths = Config::search_paths(
+            None,
+            lookup(&[
+                ("HOME", "/home/crab"),
+                ("XDG_CONFIG_DIRS", "/opt/xdg:relative"),
+            ]),
+        );
+        let dirs: Vec<&Path> = paths
+            .iter()
+            .step_by(6)
+            .map(|path| path.parent().unwrap())
+            .collect();
+        assert_eq!(
+            dirs,
+            [
+                Path::new(""),
+                Path::new("/home/crab/.config/config_reader"),
+                Path::new("/opt/xdg/config_reader"),
+                Path::new("/etc/config_reader"),
+            ]
+        );
+        assert_eq!(paths[0], Path::new("config.toml"));
+    }
+
+    #[test]
+    fn test_discovery() {
+        let home = env::temp_dir().join(format!("discovery_{}", std::process::id()));
+        let dir = home.join(".config").join(Config::APP_NAME);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.json"), "{}").unwrap();
+        let home = home.to_str().unwrap().to_string();
+
+        // The current directory is where the tests run
+        let paths = Config::search_paths(None, lookup(&[("HOME", &home)])).split_off(6);
+        let found = Config::first_existing(paths).map(|config| config.config_path);
+        let missing = Config::first_existing(vec![PathBuf::from("does/not/exist.toml")]);
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(found.unwrap(), dir.join("config.json").to_str().unwrap());
+        assert!(matches!(missing, Err(ConfigError::NotFound { searched }) if searched.len() == 1));
+    }
 }
@@ -1,11 +1,156 @@
+use crate::{diagnostic::Diagnostic, format::Format, layers::Source};
+
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
 #[allow(unused)]
 #[derive(Debug)]
 pub enum ConfigError {
     Io(std::io::Error),
     ParseIntError(std::num::ParseIntError),
     InvalidFormat(String),
+    /// There is no config file in any of the searched places, see `Config::discover`
+    NotFound {
+        searched: Vec<PathBuf>,
+    },
     InvalidValue(String, String),
     MissingSetting(String),
+    /// A setting the schema does not declare, only an error if it denies unknown settings
+    UnknownSetting(String),
+    OutOfRange {
+        key: String,
+        value: String,
+        min: Option<String>,
+        max: Option<String>,
+    },
+    /// A default or a bound of the schema is not of the declared type
+    InvalidSchema(String),
+    /// The config is not valid in its format, see `Diagnostic` for where and why
+    Parse {
+        format: Format,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// Settings that refer to each other or files that include each other, in the order they do
+    Cycle(Vec<String>),
+    /// A `${...}` in `key` that can't be resolved
+    Interpolation {
+        key: String,
+        message: String,
+    },
+    /// A setting does not fit the type it is read into, `path` is its dotted key
+    Deserialize {
+        path: String,
+        message: String,
+    },
+    /// Every problem of a config, see `ConfigReader::collect_errors`
+    Many(Vec<ConfigError>),
+    /// A setting `ConfigReader::remove` can't take out of the config file, it comes from `source`
+    NotInConfigFile {
+        key: String,
+        source: Source,
+    },
+    /// A setting `ConfigReader::remove` can't take out of the config file, it only has its default
+    Defaulted(String),
+}
+
+impl ConfigError {
+    // Parsing happens before anyone knows the file
+    pub(crate) fn with_path(self, path: &Path) -> Self {
+        match self {
+            ConfigError::Parse {
+                format,
+                diagnostics,
+            } => ConfigError::Parse {
+                format,
+                diagnostics: diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.with_path(path))
+                    .collect(),
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read the config: {e}"),
+            ConfigError::ParseIntError(e) => write!(f, "not a number: {e}"),
+            ConfigError::InvalidFormat(message) => write!(f, "invalid format: {message}"),
+            ConfigError::NotFound { searched } => {
+                write!(f, "no config file found, searched")?;
+                for path in searched {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                Ok(())
+            }
+            ConfigError::InvalidValue(key, value) => {
+                write!(f, "{key} has the invalid value {value:?}")
+            }
+            ConfigError::MissingSetting(key) => write!(f, "{key} is missing"),
+            ConfigError::UnknownSetting(key) => write!(f, "{key} is not a known setting"),
+            ConfigError::OutOfRange {
+                key,
+                value,
+                min,
+                max,
+            } => {
+                write!(f, "{key} = {value} is out of range, it has to be")?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " from {min} to {max}"),
+                    (Some(min), None) => write!(f, " at least {min}"),
+                    (None, Some(max)) => write!(f, " at most {max}"),
+                    (None, None) => write!(f, " within its range"),
+                }
+            }
+            ConfigError::InvalidSchema(message) => write!(f, "invalid schema: {message}"),
+            ConfigError::Parse {
+                format,
+                diagnostics,
+            } => {
+                write!(f, "invalid {format}")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n\n{diagnostic}")?;
+                }
+                Ok(())
+            }
+            ConfigError::Cycle(cycle) => write!(f, "cycle: {}", cycle.join(" -> ")),
+            ConfigError::Interpolation { key, message } => write!(f, "{key}: {message}"),
+            ConfigError::Deserialize { path, message } if path.is_empty() => write!(f, "{message}"),
+            ConfigError::Deserialize { path, message } => write!(f, "{path}: {message}"),
+            ConfigError::Many(errors) => {
+                write!(f, "{} problems", errors.len())?;
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+                Ok(())
+            }
+            ConfigError::NotInConfigFile { key, source } => write!(
+                f,
+                "{key} comes from the {source}, it can't be removed from the config file"
+            ),
+            ConfigError::Defaulted(key) => {
+                write!(
+                    f,
+                    "{key} only has its default value, there is nothing to remove"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(e) => Some(e),
+            ConfigError::ParseIntError(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 // From the brief example in chapter 9
@@ -0,0 +1,617 @@
+use crate::{
+    custom_error::ConfigError,
+    secret::{self, Secret},
+    value::{self, ByteSize, FromSetting},
+};
+
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess,
+    SeqAccess, VariantAccess, Visitor, value::StrDeserializer,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::BuildHasher,
+    time::Duration,
+};
+
+/// Reads flat settings into a `T`, see `ConfigReader::deserialize`
+///
+/// Dotted keys are nested tables, comma separated values and keys like `servers.0.host` are lists
+///
+/// # Errors
+///
+/// Returns `ConfigError::Deserialize` with the path of the first setting that does not fit `T`
+pub fn from_settings<T: DeserializeOwned, S: BuildHasher>(
+    settings: &HashMap<String, String, S>,
+) -> Result<T, ConfigError> {
+    from_secret_settings(settings, |_| false)
+}
+
+// Errors leave out the values of the keys `is_secret` picks
+pub(crate) fn from_secret_settings<T: DeserializeOwned, S: BuildHasher>(
+    settings: &HashMap<String, String, S>,
+    is_secret: impl Fn(&str) -> bool,
+) -> Result<T, ConfigError> {
+    let root = Node::tree(settings, is_secret);
+    T::deserialize(NodeDeserializer {
+        node: &root,
+        path: String::new(),
+    })
+}
+
+/// Reads durations like `30s` or `5m`, for `#[serde(deserialize_with = "config_reader::de::duration")]`
+///
+/// # Errors
+///
+/// Fails if the setting is no duration
+pub fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    Duration::from_setting(&raw)
+        .ok_or_else(|| de::Error::custom(format!("expected a duration, found {raw:?}")))
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SizeVisitor;
+
+        impl Visitor<'_> for SizeVisitor {
+            type Value = ByteSize;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a size like 512, 10KB or 4MiB")
+            }
+
+            fn visit_u64<E: de::Error>(self, bytes: u64) -> Result<ByteSize, E> {
+                Ok(ByteSize::new(bytes))
+            }
+
+            fn visit_str<E: de::Error>(self, raw: &str) -> Result<ByteSize, E> {
+                ByteSize::from_setting(raw)
+                    .ok_or_else(|| E::custom(format!("expected a size, found {raw:?}")))
+            }
+        }
+
+        deserializer.deserialize_any(SizeVisitor)
+    }
+}
+
+/// Plaintext, `@file:PATH` or `@env:VAR`, see `Secret`
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SecretVisitor;
+
+        impl Visitor<'_> for SecretVisitor {
+            type Value = Secret;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a secret, @file:PATH or @env:VAR")
+            }
+
+            fn visit_str<E: de::Error>(self, raw: &str) -> Result<Secret, E> {
+                Secret::resolve(raw.trim()).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(SecretVisitor)
+    }
+}
+
+impl de::Error for ConfigError {
+    // The path is added on the way out, by the table or list the error is in
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        ConfigError::Deserialize {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+}
+
+// Adds the path and redacts the value of a `secret`,
+// errors quote values as `{raw:?}` and serde quotes variants as `` `raw` ``
+fn at(error: ConfigError, path: &str, secret: Option<&str>) -> ConfigError {
+    match error {
+        ConfigError::Deserialize {
+            path: error_path,
+            mut message,
+        } => {
+            if let Some(raw) = secret {
+                message = message
+                    .replace(&format!("{raw:?}"), secret::REDACTED)
+                    .replace(&format!("`{raw}`"), secret::REDACTED);
+            }
+            ConfigError::Deserialize {
+                path: if error_path.is_empty() {
+                    path.to_string()
+                } else {
+                    error_path
+                },
+                message,
+            }
+        }
+        error => error,
+    }
+}
+
+// The dotted keys as a tree, `server.port` is the child `port` of `server`,
+// it borrows the settings so there are no copies of secrets to zero
+#[derive(Debug, Default)]
+struct Node<'a> {
+    value: Option<&'a str>,
+    secret: bool,
+    children: BTreeMap<&'a str, Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn tree<S: BuildHasher>(
+        settings: &'a HashMap<String, String, S>,
+        is_secret: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut root = Node::default();
+        for (key, value) in settings {
+            let node = key.split('.').fold(&mut root, |node, part| {
+                node.children.entry(part).or_default()
+            });
+            node.value = Some(value);
+            node.secret = is_secret(key);
+        }
+        root
+    }
+
+    fn leaf(value: &'a str, secret: bool) -> Self {
+        Self {
+            value: Some(value),
+            secret,
+            children: BTreeMap::new(),
+        }
+    }
+
+    fn secret_value(&self) -> Option<&'a str> {
+        self.value.filter(|value| self.secret && !value.is_empty())
+    }
+
+    // Children keyed 0, 1, 2 ... are a list, like the tables of a TOML array
+    fn items(&self) -> Option<Vec<&Node<'a>>> {
+        (0..self.children.len())
+            .map(|index| self.children.get(index.to_string().as_str()))
+            .collect()
+    }
+}
+
+struct NodeDeserializer<'a> {
+    node: &'a Node<'a>,
+    path: String,
+}
+
+impl<'a> NodeDeserializer<'a> {
+    fn child(&self, key: &str, node: &'a Node<'a>) -> Self {
+        let path = if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{key}", self.path)
+        };
+        Self { node, path }
+    }
+
+    fn error(&self, message: impl fmt::Display) -> ConfigError {
+        ConfigError::Deserialize {
+            path: self.path.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    fn leaf(&self, expected: &str) -> Result<&'a str, ConfigError> {
+        match self.node.value {
+            Some(value) if self.node.children.is_empty() => Ok(value),
+            _ => Err(self.error(format!("expected {expected}, found a table"))),
+        }
+    }
+
+    fn parse<T: FromSetting>(&self, expected: &str) -> Result<T, ConfigError> {
+        let raw = self.leaf(expected)?;
+        T::from_setting(raw)
+            .ok_or_else(|| self.error(format!("expected {expected}, found {raw:?}")))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $t:ty, $visit:ident;)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+            visitor.$visit(self.parse::<$t>(stringify!($t))?)
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for NodeDeserializer<'_> {
+    type Error = ConfigError;
+
+    // Without a type to go by, settings are strings and tables are maps
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        match self.node.value {
+            Some(value) if self.node.children.is_empty() => visitor.visit_str(value),
+            _ => self.deserialize_map(visitor),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => bool, visit_bool;
+        deserialize_i8 => i8, visit_i8;
+        deserialize_i16 => i16, visit_i16;
+        deserialize_i32 => i32, visit_i32;
+        deserialize_i64 => i64, visit_i64;
+        deserialize_u8 => u8, visit_u8;
+        deserialize_u16 => u16, visit_u16;
+        deserialize_u32 => u32, visit_u32;
+        deserialize_u64 => u64, visit_u64;
+        deserialize_f32 => f32, visit_f32;
+        deserialize_f64 => f64, visit_f64;
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        let raw = self.leaf("a char")?;
+        let mut chars = raw.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.error(format!("expected a char, found {raw:?}"))),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        visitor.visit_str(self.leaf("a string")?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        visitor.visit_bytes(self.leaf("bytes")?.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    // Missing settings are `None` too, that's up to the struct they are in
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        match self.node.value {
+            Some(value) if value.is_empty() && self.node.children.is_empty() => {
+                visitor.visit_none()
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // A list is either comma separated or has the children 0, 1, 2 ...
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        let split: Vec<Node>;
+        let items = if self.node.children.is_empty() {
+            split = value::list_items(self.leaf("a list")?)
+                .map(|item| Node::leaf(item, self.node.secret))
+                .collect();
+            split.iter().collect()
+        } else {
+            self.node
+                .items()
+                .ok_or_else(|| self.error("expected a list, found a table"))?
+        };
+
+        let items: Vec<NodeDeserializer> = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, node)| self.child(&index.to_string(), node))
+            .collect();
+        visitor.visit_seq(Seq {
+            items: items.into_iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        if let (Some(value), true) = (self.node.value, self.node.children.is_empty()) {
+            return Err(self.error(format!("expected a table, found {value:?}")));
+        }
+        let entries: Vec<(&str, NodeDeserializer)> = self
+            .node
+            .children
+            .iter()
+            .map(|(key, node)| (*key, self.child(key, node)))
+            .collect();
+        visitor.visit_map(Map {
+            entries: entries.into_iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.deserialize_map(visitor)
+    }
+
+    // A unit variant is a setting like `mode = fast`, others are a table with one key
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        if self.node.children.is_empty() {
+            return visitor
+                .visit_enum(StrDeserializer::<ConfigError>::new(self.leaf("a variant")?));
+        }
+        let mut children = self.node.children.iter();
+        match (children.next(), children.next()) {
+            (Some((variant, node)), None) => visitor.visit_enum(Enum {
+                variant,
+                value: self.child(variant, node),
+            }),
+            _ => Err(self.error("expected a variant, found a table with several keys")),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfigError> {
+        visitor.visit_unit()
+    }
+}
+
+struct Seq<'a> {
+    items: std::vec::IntoIter<NodeDeserializer<'a>>,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_> {
+    type Error = ConfigError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ConfigError> {
+        self.items
+            .next()
+            .map(|item| {
+                let (path, secret) = (item.path.clone(), item.node.secret_value());
+                seed.deserialize(item).map_err(|e| at(e, &path, secret))
+            })
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct Map<'a> {
+    entries: std::vec::IntoIter<(&'a str, NodeDeserializer<'a>)>,
+    value: Option<NodeDeserializer<'a>>,
+}
+
+impl<'de> MapAccess<'de> for Map<'_> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConfigError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let path = value.path.clone();
+        self.value = Some(value);
+        seed.deserialize(StrDeserializer::<ConfigError>::new(key))
+            .map(Some)
+            .map_err(|e| at(e, &path, None))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ConfigError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <ConfigError as de::Error>::custom("a value was read before its key"))?;
+        let (path, secret) = (value.path.clone(), value.node.secret_value());
+        seed.deserialize(value).map_err(|e| at(e, &path, secret))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct Enum<'a> {
+    variant: &'a str,
+    value: NodeDeserializer<'a>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for Enum<'a> {
+    type Error = ConfigError;
+    type Variant = NodeDeserializer<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), ConfigError> {
+        let variant = seed.deserialize(StrDeserializer::<ConfigError>::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for NodeDeserializer<'_> {
+    type Error = ConfigError;
+
+    fn unit_variant(self) -> Result<(), ConfigError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ConfigError> {
+        let (path, secret) = (self.path.clone(), self.node.secret_value());
+        seed.deserialize(self).map_err(|e| at(e, &path, secret))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfigError> {
+        self.deserialize_map(visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::format::Format;
+
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct AppConfig {
+        name: String,
+        debug: Option<bool>,
+        retries: Option<u32>,
+        #[serde(deserialize_with = "duration")]
+        timeout: Duration,
+        cache: ByteSize,
+        mode: Mode,
+        database: Database,
+        servers: Vec<Server>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Database {
+        host: String,
+        port: u16,
+        replicas: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        weight: f64,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        #[allow(unused)]
+        Fast,
+        Safe,
+    }
+
+    const TOML: &str = r#"
+        name = "crab"
+        debug = true
+        timeout = "5m"
+        cache = "4MiB"
+        mode = "safe"
+        [database]
+        host = "localhost"
+        port = 5432
+        replicas = ["a", "b"]
+        [[servers]]
+        weight = 0.5
+        [[servers]]
+        weight = 1.5
+    "#;
+
+    fn error_of(text: &str) -> String {
+        let settings = Format::Toml.parse(text).unwrap();
+        from_settings::<AppConfig, _>(&settings)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_nested_structs_and_lists() {
+        let config: AppConfig = from_settings(&Format::Toml.parse(TOML).unwrap()).unwrap();
+        assert_eq!(config.name, "crab");
+        assert_eq!((config.debug, config.retries), (Some(true), None));
+        assert_eq!(config.timeout, Duration::from_mins(5));
+        assert_eq!(config.cache, ByteSize::new(4 << 20));
+        assert_eq!(config.mode, Mode::Safe);
+        assert_eq!(config.database.host, "localhost");
+        assert_eq!(config.database.port, 5432);
+        assert_eq!(config.database.replicas, ["a", "b"]);
+        assert_eq!(
+            config.servers,
+            [Server { weight: 0.5 }, Server { weight: 1.5 }]
+        );
+    }
+
+    #[test]
+    fn test_errors_have_paths() {
+        assert_eq!(
+            error_of(&TOML.replace("port = 5432", "port = 70000")),
+            "database.port: expected u16, found \"70000\""
+        );
+        assert_eq!(
+            error_of(&TOML.replace("weight = 1.5", "weight = \"heavy\"")),
+            "servers.1.weight: expected f64, found \"heavy\""
+        );
+        assert_eq!(
+            error_of(&TOML.replace("host = \"localhost\"", "")),
+            "database: missing field `host`"
+        );
+        assert_eq!(
+            error_of(&TOML.replace("timeout = \"5m\"", "timeout = \"soon\"")),
+            "timeout: expected a duration, found \"soon\""
+        );
+        assert!(
+            error_of(&TOML.replace("\"safe\"", "\"slow\"")).starts_with("mode: unknown variant")
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Where and why a config is broken, displayed with the offending line and a caret under it
+///
+/// ```text
+/// error: a section needs a closing ]
+///  --> config.ini:2:1
+///   |
+/// 2 | [server
+///   | ^^^^^^^
+///   = hint: write a section as [name]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    path: Option<PathBuf>,
+    line: usize,
+    columns: Range<usize>,
+    message: String,
+    hint: Option<String>,
+    source_line: String,
+}
+
+impl Diagnostic {
+    /// `span` are the byte offsets of the problem in `source_line`, the `line` starts at 1
+    #[must_use]
+    pub fn new(line: usize, source_line: &str, span: Range<usize>, message: &str) -> Self {
+        let start = column(source_line, span.start);
+        // The caret is at least one character wide, even for an empty span at the end of a line
+        let end = column(source_line, span.end).max(start + 1);
+
+        Self {
+            path: None,
+            line,
+            columns: start..end,
+            message: message.to_string(),
+            hint: None,
+            source_line: source_line.to_string(),
+        }
+    }
+
+    /// The problem is at the byte offsets `span` of the whole `text`
+    #[must_use]
+    pub fn in_text(text: &str, span: Range<usize>, message: &str) -> Self {
+        let start = floor_char_boundary(text, span.start.min(text.len()));
+        let line_start = text[..start].rfind('\n').map_or(0, |at| at + 1);
+        let line_end = text[start..].find('\n').map_or(text.len(), |at| start + at);
+        let source_line = text[line_start..line_end].trim_end_matches('\r');
+        let line = text[..line_start].matches('\n').count() + 1;
+        let end = floor_char_boundary(text, span.end.clamp(start, line_end));
+
+        Self::new(
+            line,
+            source_line,
+            (start - line_start).min(source_line.len())..(end - line_start).min(source_line.len()),
+            message,
+        )
+    }
+
+    /// The problem is at the character `column` of `line` in `text`, both start at 1
+    #[must_use]
+    pub fn at(text: &str, line: usize, column: usize, message: &str) -> Self {
+        let source_line = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+        let start = source_line
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(source_line.len(), |(at, _)| at);
+
+        Self::new(line.max(1), source_line, start..start, message)
+    }
+
+    #[must_use]
+    pub fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Starts at 1
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The characters of the line the problem spans, starting at 1
+    #[must_use]
+    pub fn columns(&self) -> Range<usize> {
+        self.columns.clone()
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[must_use]
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        let path = self
+            .path
+            .as_ref()
+            .map_or_else(|| "<config>".to_string(), |path| path.display().to_string());
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{gutter}--> {path}:{}:{}", self.line, self.columns.start)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(self.columns.start - 1),
+            "^".repeat(self.columns.len())
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n{gutter} = hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+// The column of a byte offset, counted in characters from 1
+fn column(line: &str, offset: usize) -> usize {
+    line[..floor_char_boundary(line, offset.min(line.len()))]
+        .chars()
+        .count()
+        + 1
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let diagnostic = Diagnostic::new(12, "[server", 0..7, "a section needs a closing ]")
+            .with_hint("write a section as [name]")
+            .with_path(Path::new("app.ini"));
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: a section needs a closing ]\n  --> app.ini:12:1\n   |\n12 | [server\n   | ^^^^^^^\n   = hint: write a section as [name]"
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let text = "a = 1\nbé = \"x\" y\n";
+        let diagnostic = Diagnostic::in_text(text, 16..17, "trailing");
+        assert_eq!(diagnostic.line(), 2);
+        assert_eq!(diagnostic.columns(), 10..11);
+
+        let diagnostic = Diagnostic::at(text, 2, 10, "trailing");
+        assert_eq!((diagnostic.line(), diagnostic.columns()), (2, 10..11));
+        // A line past the end of the text is shown empty
+        assert_eq!(Diagnostic::at(text, 9, 1, "eof").columns(), 1..2);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::format::ini;
+
+use std::{fmt, fs, io, ops::Range, path::Path, process};
+
+/// An INI file that can be edited without losing its comments, order and spacing
+///
+/// Only the lines of the settings that are set or removed change
+///
+/// ```
+/// # use config_reader::document::IniDocument;
+/// let mut document = IniDocument::parse("; retries\nmax_retries = 3 ; at most 10\n");
+/// document.set("max_retries", "5");
+/// document.set("server.port", "8080");
+/// assert_eq!(
+///     document.to_string(),
+///     "; retries\nmax_retries = 5 ; at most 10\n\n[server]\nport = 8080\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IniDocument {
+    lines: Vec<String>,
+    newline: &'static str,
+    ends_with_newline: bool,
+}
+
+impl IniDocument {
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+            newline: if text.contains("\r\n") { "\r\n" } else { "\n" },
+            ends_with_newline: text.is_empty() || text.ends_with('\n'),
+        }
+    }
+
+    /// Changes every line of `key`, a new setting goes to the end of its section
+    ///
+    /// `server.port` is `port` in `[server]`, a section that does not exist yet is added
+    pub fn set(&mut self, key: &str, value: &str) {
+        let value = ini::quote(value);
+        let lines: Vec<(usize, Range<usize>)> = self.lines_of(key).collect();
+        if lines.is_empty() {
+            self.insert(key, &value);
+        }
+        for (index, span) in lines {
+            self.lines[index].replace_range(span, &value);
+        }
+    }
+
+    /// Removes every line of `key`, `false` if there is none
+    pub fn remove(&mut self, key: &str) -> bool {
+        let lines: Vec<usize> = self.lines_of(key).map(|(index, _)| index).collect();
+        for index in lines.iter().rev() {
+            self.lines.remove(*index);
+        }
+        !lines.is_empty()
+    }
+
+    // The line numbers of `key` and the bytes of its value in them
+    fn lines_of<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (usize, Range<usize>)> + 'a {
+        let mut section = String::new();
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, line)| {
+                if let Some(name) = ini::section_name(line) {
+                    section = format!("{name}.");
+                    return None;
+                }
+                let (name, span) = ini::setting_span(line)?;
+                (format!("{section}{name}") == key).then_some((index, span))
+            })
+    }
+
+    // Into the section with the longest name `key` starts with, after its last setting
+    fn insert(&mut self, key: &str, value: &str) {
+        // Without settings at the top level, new ones go before the blank lines above the first section
+        let mut top_level_end = self
+            .lines
+            .iter()
+            .position(|line| ini::section_name(line).is_some())
+            .unwrap_or(self.lines.len());
+        while top_level_end > 0 && self.lines[top_level_end - 1].trim().is_empty() {
+            top_level_end -= 1;
+        }
+
+        // (length of the section name, line after its last setting), the top level has no name
+        let mut best = (0, top_level_end);
+        let mut current = Some(0);
+        for (index, line) in self.lines.iter().enumerate() {
+            if let Some(name) = ini::section_name(line) {
+                current = key
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with('.'))
+                    .then_some(name.len())
+                    .filter(|length| *length >= best.0);
+                if let Some(length) = current {
+                    best = (length, index + 1);
+                }
+            } else if ini::setting_span(line).is_some()
+                && let Some(length) = current
+            {
+                best = (length, index + 1);
+            }
+        }
+
+        let separator = self.separator();
+        match best {
+            (0, _) if key.contains('.') => {
+                let (section, name) = key.rsplit_once('.').unwrap_or_default();
+                if self
+                    .lines
+                    .last()
+                    .is_some_and(|line| !line.trim().is_empty())
+                {
+                    self.lines.push(String::new());
+                }
+                self.lines.push(format!("[{section}]"));
+                self.lines.push(format!("{name}{separator}{value}"));
+            }
+            (length, at) => {
+                let name = if length == 0 { key } else { &key[length + 1..] };
+                self.lines.insert(at, format!("{name}{separator}{value}"));
+            }
+        }
+    }
+
+    // What's between the key and the value of the first setting, ` = ` without one
+    fn separator(&self) -> String {
+        self.lines
+            .iter()
+            .find_map(|line| {
+                let (key, span) = ini::setting_span(line)?;
+                let key_end = line.find(key)? + key.len();
+                Some(line[key_end..span.start].to_string())
+            })
+            .unwrap_or_else(|| " = ".to_string())
+    }
+}
+
+impl fmt::Display for IniDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines.join(self.newline))?;
+        if self.ends_with_newline && !self.lines.is_empty() {
+            write!(f, "{}", self.newline)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it to `path`,
+/// so readers see either the old or the new file and never a half written one
+///
+/// # Errors
+///
+/// Returns the error of writing or renaming the temporary file, `path` is unchanged then
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the path has no file name"))?;
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+
+    let result = (|| {
+        fs::write(&temp, contents)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        fs::File::open(&temp)?.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "; the app\r\nname=crab\r\n\r\n[server]\r\n# where\r\nport=80 ; web\r\n\r\n[server.tls]\r\ncert=a.pem\r\n";
+
+    #[test]
+    fn test_untouched_lines_stay() {
+        let mut document = IniDocument::parse(TEXT);
+        assert_eq!(document.to_string(), TEXT);
+
+        document.set("server.port", "443");
+        document.set("server.host", "0.0.0.0");
+        document.set("server.tls.key", "b.pem");
+        document.set("version", "a ; b");
+        assert!(document.remove("name"));
+        assert!(!document.remove("nothing"));
+        assert_eq!(
+            document.to_string(),
+            "; the app\r\nversion=\"a ; b\"\r\n\r\n[server]\r\n# where\r\nport=443 ; web\r\nhost=0.0.0.0\r\n\r\n[server.tls]\r\ncert=a.pem\r\nkey=b.pem\r\n"
+        );
+    }
+
+    #[test]
+    fn test_new_sections() {
+        let mut document = IniDocument::parse("a = 1");
+        document.set("db.port", "5432");
+        assert_eq!(document.to_string(), "a = 1\n\n[db]\nport = 5432");
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let path = std::env::temp_dir().join(format!("atomic_{}.ini", process::id()));
+        fs::write(&path, "old").unwrap();
+        write_atomically(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        fs::remove_file(&path).unwrap();
+    }
+}
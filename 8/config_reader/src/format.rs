@@ -0,0 +1,250 @@
+use crate::{custom_error::ConfigError, diagnostic::Diagnostic};
+
+use std::{collections::HashMap, fmt, path::Path};
+
+pub(crate) mod ini;
+
+/// The file formats a config can be written in
+///
+/// Every format ends up as the same flat settings: nested tables and INI sections
+/// become dotted keys like `server.port`, lists of plain values become comma separated values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `key = value` lines in `[sections]`, the plain `key=value` files are INI too
+    Ini,
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// Tells the format by the extension, `.txt` files are read as INI
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ini" | "cfg" | "conf" | "txt" => Some(Format::Ini),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Parse` with the first problem if `text` is not valid in this format
+    /// or its top level is not a table
+    pub fn parse(self, text: &str) -> Result<HashMap<String, String>, ConfigError> {
+        self.parse_with(text, false)
+    }
+
+    /// Like `parse`, but reports every broken line of an INI file
+    ///
+    /// The other formats can't be read on after their first problem
+    ///
+    /// # Errors
+    ///
+    /// See `parse`
+    pub fn parse_all(self, text: &str) -> Result<HashMap<String, String>, ConfigError> {
+        self.parse_with(text, true)
+    }
+
+    fn parse_with(
+        self,
+        text: &str,
+        collect_all: bool,
+    ) -> Result<HashMap<String, String>, ConfigError> {
+        let tree: serde_json::Value = match self {
+            Format::Ini => {
+                return ini::parse(text, collect_all)
+                    .map_err(|diagnostics| self.error(diagnostics));
+            }
+            Format::Toml => toml::from_str(text).map_err(|e| {
+                let diagnostic = match e.span() {
+                    Some(span) => Diagnostic::in_text(text, span, e.message()),
+                    None => Diagnostic::at(text, 1, 1, e.message()),
+                };
+                self.error(vec![diagnostic])
+            })?,
+            Format::Json => serde_json::from_str(text).map_err(|e| {
+                let message = e.to_string();
+                self.error(vec![Diagnostic::at(
+                    text,
+                    e.line(),
+                    e.column(),
+                    without_location(&message),
+                )])
+            })?,
+            Format::Yaml => serde_norway::from_str(text).map_err(|e| {
+                let message = e.to_string();
+                let (line, column) = e
+                    .location()
+                    .map_or((1, 1), |location| (location.line(), location.column()));
+                self.error(vec![Diagnostic::at(
+                    text,
+                    line,
+                    column,
+                    without_location(&message),
+                )])
+            })?,
+        };
+
+        let serde_json::Value::Object(table) = tree else {
+            let start = text.len() - text.trim_start().len();
+            let diagnostic =
+                Diagnostic::in_text(text, start..start, "the top level has to be a table")
+                    .with_hint("map the names of the settings to their values");
+            return Err(self.error(vec![diagnostic]));
+        };
+        let mut settings = HashMap::new();
+        for (key, value) in table {
+            flatten(&mut settings, key, value);
+        }
+        Ok(settings)
+    }
+
+    fn error(self, diagnostics: Vec<Diagnostic>) -> ConfigError {
+        ConfigError::Parse {
+            format: self,
+            diagnostics,
+        }
+    }
+}
+
+// The diagnostic shows the location, JSON and YAML errors end with it
+fn without_location(message: &str) -> &str {
+    message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message)
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Ini => write!(f, "INI"),
+            Format::Toml => write!(f, "TOML"),
+            Format::Json => write!(f, "JSON"),
+            Format::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+// Lists of tables or lists get indexed keys like `servers.0.host`
+fn flatten(settings: &mut HashMap<String, String>, key: String, value: serde_json::Value) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(table) => {
+            for (child, value) in table {
+                flatten(settings, format!("{key}.{child}"), value);
+            }
+        }
+        Value::Array(items) if items.iter().any(|item| item.is_object() || item.is_array()) => {
+            for (index, item) in items.into_iter().enumerate() {
+                flatten(settings, format!("{key}.{index}"), item);
+            }
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.into_iter().map(scalar).collect();
+            settings.insert(key, items.join(","));
+        }
+        value => {
+            settings.insert(key, scalar(value));
+        }
+    }
+}
+
+fn scalar(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text,
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expected() -> HashMap<String, String> {
+        [
+            ("name", "crab"),
+            ("server.port", "8080"),
+            ("server.debug", "true"),
+            ("server.hosts", "a.example,b.example"),
+            ("servers.0.weight", "0.5"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_formats_agree() {
+        let toml = r#"
+            name = "crab"
+            [server]
+            port = 8080
+            debug = true
+            hosts = ["a.example", "b.example"]
+            [[servers]]
+            weight = 0.5
+        "#;
+        let json = r#"{
+            "name": "crab",
+            "server": { "port": 8080, "debug": true, "hosts": ["a.example", "b.example"] },
+            "servers": [{ "weight": 0.5 }]
+        }"#;
+        let yaml = "
+name: crab
+server:
+  port: 8080
+  debug: true
+  hosts: [a.example, b.example]
+servers:
+  - weight: 0.5
+";
+        assert_eq!(Format::Toml.parse(toml).unwrap(), expected());
+        assert_eq!(Format::Json.parse(json).unwrap(), expected());
+        assert_eq!(Format::Yaml.parse(yaml).unwrap(), expected());
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Format::from_path(Path::new("app.YML")), Some(Format::Yaml));
+        assert_eq!(
+            Format::from_path(Path::new("src/config.txt")),
+            Some(Format::Ini)
+        );
+        assert_eq!(Format::from_path(Path::new("config")), None);
+    }
+
+    #[test]
+    fn test_top_level_has_to_be_a_table() {
+        let result = Format::Json.parse("[1, 2]");
+        assert!(matches!(
+            result,
+            Err(ConfigError::Parse {
+                format: Format::Json,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        let location = |format: Format, text: &str| {
+            let Err(ConfigError::Parse { diagnostics, .. }) = format.parse(text) else {
+                panic!("expected a parse error");
+            };
+            (diagnostics[0].line(), diagnostics[0].columns().start)
+        };
+        assert_eq!(location(Format::Toml, "a = 1\nb = = 2\n"), (2, 5));
+        assert_eq!(
+            location(Format::Json, "{\n  \"a\": 1,\n  \"b\" 2\n}"),
+            (3, 7)
+        );
+        assert_eq!(location(Format::Yaml, "a: 1\nb: [1, 2\n"), (3, 1));
+    }
+}
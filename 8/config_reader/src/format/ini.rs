@@ -0,0 +1,314 @@
+use crate::diagnostic::Diagnostic;
+
+use std::{collections::HashMap, ops::Range};
+
+// The INI dialect:
+// - blank lines and lines starting with `;` or `#` are skipped
+// - `[section]` puts the following keys under `section.`
+// - `key = value` splits at the first `=`, so values may contain `=`
+// - unquoted values end at a ` ;` or ` #` comment
+// - "double quoted" values know the escapes \\ \" \n \t \r \0, 'single quoted' ones are literal
+//
+// Every broken line is reported if `collect_all`, only the first one otherwise
+pub(super) fn parse(
+    text: &str,
+    collect_all: bool,
+) -> Result<HashMap<String, String>, Vec<Diagnostic>> {
+    let mut settings = HashMap::new();
+    let mut section = String::new();
+    let mut diagnostics = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if let Err(problem) = parse_line(line, &mut section, &mut settings) {
+            diagnostics.push(
+                Diagnostic::new(index + 1, line, problem.span, problem.message)
+                    .with_hint(problem.hint),
+            );
+            if !collect_all {
+                break;
+            }
+        }
+    }
+    if diagnostics.is_empty() {
+        Ok(settings)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+// `span` are byte offsets in the line, or in the value while parsing it
+struct Problem {
+    span: Range<usize>,
+    message: &'static str,
+    hint: &'static str,
+}
+
+impl Problem {
+    fn new(span: Range<usize>, message: &'static str, hint: &'static str) -> Self {
+        Self {
+            span,
+            message,
+            hint,
+        }
+    }
+}
+
+fn parse_line(
+    line: &str,
+    section: &mut String,
+    settings: &mut HashMap<String, String>,
+) -> Result<(), Problem> {
+    let start = line.len() - line.trim_start().len();
+    let line = line.trim();
+    let whole_line = start..start + line.len();
+    if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+        return Ok(());
+    }
+
+    if let Some(header) = line.strip_prefix('[') {
+        let Some(name) = header.strip_suffix(']').map(str::trim) else {
+            return Err(Problem::new(
+                whole_line,
+                "a section needs a closing ]",
+                "write a section as [name]",
+            ));
+        };
+        if name.is_empty() {
+            return Err(Problem::new(
+                whole_line,
+                "a section needs a name",
+                "write a section as [name]",
+            ));
+        }
+        *section = format!("{name}.");
+        return Ok(());
+    }
+
+    let Some(equals) = line.find('=') else {
+        return Err(Problem::new(
+            whole_line,
+            "expected key = value",
+            "write a setting as key = value, or start a comment with ; or #",
+        ));
+    };
+    let key = line[..equals].trim();
+    if key.is_empty() {
+        return Err(Problem::new(
+            start + equals..start + equals + 1,
+            "a setting needs a key",
+            "put the name of the setting before the =",
+        ));
+    }
+    let value = &line[equals + 1..];
+    let value_start = start + equals + 1 + value.len() - value.trim_start().len();
+    let value = parse_value(value.trim()).map_err(|problem| Problem {
+        span: value_start + problem.span.start..value_start + problem.span.end,
+        ..problem
+    })?;
+    settings.insert(format!("{section}{key}"), value);
+    Ok(())
+}
+
+fn parse_value(value: &str) -> Result<String, Problem> {
+    let unclosed = || {
+        Problem::new(
+            0..value.len(),
+            "a quoted value needs a closing quote",
+            "end the value with the quote it starts with",
+        )
+    };
+    let (parsed, rest) = match value.chars().next() {
+        Some('"') => unescape(&value[1..]).map_err(|problem| {
+            problem.map_or_else(unclosed, |at| {
+                Problem::new(
+                    1 + at..(1 + at + 2).min(value.len()),
+                    "unknown escape sequence",
+                    "the escapes are \\\\ \\\" \\n \\t \\r \\0, 'single quoted' values are literal",
+                )
+            })
+        })?,
+        Some('\'') => {
+            let Some((literal, rest)) = value[1..].split_once('\'') else {
+                return Err(unclosed());
+            };
+            (literal.to_string(), rest)
+        }
+        _ => return Ok(strip_comment(value).to_string()),
+    };
+
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with(';') || rest.starts_with('#') {
+        Ok(parsed)
+    } else {
+        Err(Problem::new(
+            value.len() - rest.len()..value.len(),
+            "only a comment may follow a quoted value",
+            "put the whole value in the quotes, or start a comment with ; or #",
+        ))
+    }
+}
+
+// Returns the unescaped value and what follows its closing quote, or where an unknown escape
+// starts, `None` if there is no closing quote
+fn unescape(quoted: &str) -> Result<(String, &str), Option<usize>> {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &quoted[at + 1..])),
+            '\\' => value.push(match chars.next() {
+                Some((_, '\\')) => '\\',
+                Some((_, '"')) => '"',
+                Some((_, 'n')) => '\n',
+                Some((_, 't')) => '\t',
+                Some((_, 'r')) => '\r',
+                Some((_, '0')) => '\0',
+                Some(_) => return Err(Some(at)),
+                None => return Err(None),
+            }),
+            c => value.push(c),
+        }
+    }
+    Err(None)
+}
+
+/// The key and the bytes of the value of a `key = value` line, `None` for other lines
+pub(crate) fn setting_span(line: &str) -> Option<(&str, Range<usize>)> {
+    let start = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with([';', '#', '[']) {
+        return None;
+    }
+    let equals = trimmed.find('=')?;
+    let value = &trimmed[equals + 1..];
+    let value_start = start + equals + 1 + value.len() - value.trim_start().len();
+    let value = value.trim();
+    // A broken quoted value is replaced up to the end of the line
+    let len = match value.chars().next() {
+        Some('"') => {
+            unescape(&value[1..]).map_or(value.len(), |(_, rest)| value.len() - rest.len())
+        }
+        Some('\'') => value[1..].find('\'').map_or(value.len(), |at| at + 2),
+        _ => strip_comment(value).len(),
+    };
+    Some((trimmed[..equals].trim(), value_start..value_start + len))
+}
+
+/// The name of a `[section]` line
+pub(crate) fn section_name(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+/// `value` as it is written in a file, quoted if it would not read back the same otherwise
+pub(crate) fn quote(value: &str) -> String {
+    let plain = value == value.trim()
+        && !value.starts_with(['"', '\''])
+        && !value.contains(['\n', '\r', '\0'])
+        && strip_comment(value) == value;
+    if plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn strip_comment(value: &str) -> &str {
+    let end = value
+        .char_indices()
+        .find(|&(at, c)| (c == ';' || c == '#') && value[..at].ends_with(char::is_whitespace))
+        .map_or(value.len(), |(at, _)| at);
+    value[..end].trim_end()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sections_and_comments() {
+        let settings = parse(
+            "; global settings\nname = crab ; the name\n\n[server]\n# where to listen\nurl = http://x?a=b#top\n",
+            false,
+        )
+        .unwrap();
+        assert_eq!(settings["name"], "crab");
+        assert_eq!(settings["server.url"], "http://x?a=b#top");
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let settings = parse(
+            "greeting = \"hi \\\"crab\\\"\\n ; still\" ; comment\npath = 'C:\\dir'\n",
+            false,
+        )
+        .unwrap();
+        assert_eq!(settings["greeting"], "hi \"crab\"\n ; still");
+        assert_eq!(settings["path"], "C:\\dir");
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        let diagnostics = parse("a = 1\n  [broken\n", false).unwrap_err();
+        assert_eq!(diagnostics[0].line(), 2);
+        assert_eq!(diagnostics[0].columns(), 3..10);
+
+        let columns = |line: &str| parse(line, false).unwrap_err()[0].columns();
+        assert_eq!(columns("a = \"x\" y"), 9..10);
+        assert_eq!(columns("a = \"x\\q\""), 7..9);
+        assert_eq!(columns("a = 'open"), 5..10);
+        assert_eq!(columns(" = 1"), 2..3);
+        assert_eq!(columns("just words"), 1..11);
+    }
+
+    #[test]
+    fn test_quote_reads_back() {
+        for value in [
+            "plain",
+            "a ; b",
+            " padded ",
+            "\"quoted\"",
+            "two\nlines",
+            "C:\\dir",
+            "",
+        ] {
+            let settings = parse(&format!("key = {}", quote(value)), false).unwrap();
+            assert_eq!(settings["key"], value);
+        }
+        assert_eq!(quote("http://x#top"), "http://x#top");
+    }
+
+    #[test]
+    fn test_setting_span() {
+        let line = "  port = \"80\" ; web";
+        let (key, span) = setting_span(line).unwrap();
+        assert_eq!((key, &line[span]), ("port", "\"80\""));
+        assert_eq!(setting_span("[port]"), None);
+        assert_eq!(section_name(" [ server ] "), Some("server"));
+    }
+
+    #[test]
+    fn test_collect_all() {
+        let text = "[\na = 1\nwords\nb = \"open\n";
+        assert_eq!(parse(text, false).unwrap_err().len(), 1);
+        let lines: Vec<usize> = parse(text, true)
+            .unwrap_err()
+            .iter()
+            .map(Diagnostic::line)
+            .collect();
+        assert_eq!(lines, [1, 3, 4]);
+    }
+}
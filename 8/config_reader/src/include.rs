@@ -0,0 +1,175 @@
+use crate::{custom_error::ConfigError, format::Format, value};
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// `include = "path"` reads another config file first, the including file overrides its settings
+///
+/// The path is relative to the including file, several are comma separated or a list
+pub const INCLUDE_KEY: &str = "include";
+
+/// A setting, its value and the file it is from
+pub(crate) type FileSetting = (String, String, PathBuf);
+
+/// Reads a config file and the files it includes, in the order they override each other
+///
+/// `format` is the format of `path` if it is not the one of its extension,
+/// included files are always read in the format of their extension
+///
+/// # Errors
+///
+/// Returns `ConfigError::Io` if a file can't be read, `ConfigError::InvalidFormat`
+/// if the format of a file can't be told, `ConfigError::Parse` if a file is not valid in it
+/// or `ConfigError::Cycle` if files include each other
+pub(crate) fn read_file(
+    path: &Path,
+    format: Option<Format>,
+    collect_all: bool,
+) -> Result<Vec<FileSetting>, ConfigError> {
+    read_files(path, format, collect_all).map(|(settings, _)| settings)
+}
+
+/// Like `read_file`, but also returns every file it read, also the ones without settings
+pub(crate) fn read_files(
+    path: &Path,
+    format: Option<Format>,
+    collect_all: bool,
+) -> Result<(Vec<FileSetting>, Vec<PathBuf>), ConfigError> {
+    let (mut settings, mut paths) = (Vec::new(), Vec::new());
+    read(
+        path,
+        format,
+        collect_all,
+        &mut Vec::new(),
+        &mut settings,
+        &mut paths,
+    )?;
+    Ok((settings, paths))
+}
+
+// `including` are the files on the way to `path`
+fn read(
+    path: &Path,
+    format: Option<Format>,
+    collect_all: bool,
+    including: &mut Vec<PathBuf>,
+    settings: &mut Vec<FileSetting>,
+    paths: &mut Vec<PathBuf>,
+) -> Result<(), ConfigError> {
+    let Some(format) = format.or_else(|| Format::from_path(path)) else {
+        return Err(ConfigError::InvalidFormat(format!(
+            "Can't tell the format of {}, please set one",
+            path.display()
+        )));
+    };
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+    paths.push(path.to_path_buf());
+    let parsed = if collect_all {
+        format.parse_all(&text)
+    } else {
+        format.parse(&text)
+    };
+    let mut parsed = parsed.map_err(|e| e.with_path(path))?;
+
+    let canonical = fs::canonicalize(path)?;
+    if including.contains(&canonical) {
+        let mut cycle: Vec<String> = including
+            .iter()
+            .skip_while(|seen| **seen != canonical)
+            .map(|seen| seen.display().to_string())
+            .collect();
+        cycle.push(canonical.display().to_string());
+        return Err(ConfigError::Cycle(cycle));
+    }
+
+    if let Some(includes) = parsed.remove(INCLUDE_KEY) {
+        including.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for include in value::list_items(&includes) {
+            read(
+                &dir.join(include),
+                None,
+                collect_all,
+                including,
+                settings,
+                paths,
+            )?;
+        }
+        including.pop();
+    }
+
+    let mut own: Vec<FileSetting> = parsed
+        .into_iter()
+        .map(|(key, value)| (key, value, path.to_path_buf()))
+        .collect();
+    // Settings come out of a HashMap, the order only matters between files
+    own.sort();
+    settings.extend(own);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{name}_{}", std::process::id()));
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_includes_are_relative_and_overridden() {
+        let dir = temp_dir("include");
+        fs::write(
+            dir.join("main.toml"),
+            "include = [\"conf.d/db.ini\"]\nport = 8080\n[db]\nport = 6543\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("conf.d/db.ini"),
+            "include = base.json\n[db]\nhost = localhost\nport = 5432\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("conf.d/base.json"),
+            r#"{"db": {"host": "db.example"}}"#,
+        )
+        .unwrap();
+
+        let settings = read_file(&dir.join("main.toml"), None, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let order: Vec<(&str, &str)> = settings
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|(key, value, _)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("db.host", "db.example"),
+                ("db.host", "localhost"),
+                ("db.port", "5432"),
+                ("db.port", "6543"),
+                ("port", "8080"),
+            ]
+        );
+        assert!(settings.unwrap()[0].2.ends_with("conf.d/base.json"));
+    }
+
+    #[test]
+    fn test_include_cycles() {
+        let dir = temp_dir("include_cycle");
+        fs::write(dir.join("a.ini"), "include = conf.d/b.ini\n").unwrap();
+        fs::write(dir.join("conf.d/b.ini"), "include = ../a.ini\n").unwrap();
+
+        let result = read_file(&dir.join("a.ini"), None, false);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(ConfigError::Cycle(cycle)) if cycle.len() == 3));
+    }
+}
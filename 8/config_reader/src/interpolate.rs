@@ -0,0 +1,206 @@
+use crate::{custom_error::ConfigError, secret::Secret};
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+};
+
+/// Replaces `${other_key}` with the value of another setting and `${env:VAR}` with the value
+/// of an environment variable in the settings `keys`, `$$` is a plain `$`
+///
+/// Settings not in `keys` are taken as they are, they have been interpolated before.
+/// The settings `secret` picks and the ones that refer to a file or variable are secrets,
+/// only a secret can refer to a secret and nothing to a file or variable
+///
+/// # Errors
+///
+/// Returns `ConfigError::Cycle` if settings refer to each other in a circle,
+/// or `ConfigError::Interpolation` for an unknown setting, an unset variable, an unclosed `${`
+/// or a secret that would end up in a setting that is none
+pub fn interpolate<S: BuildHasher>(
+    values: &mut HashMap<String, String, S>,
+    keys: impl IntoIterator<Item = String>,
+    env: &dyn Fn(&str) -> Option<String>,
+    secret: &dyn Fn(&str) -> bool,
+) -> Result<(), ConfigError> {
+    let mut pending: HashSet<String> = keys
+        .into_iter()
+        .filter(|key| values.get(key).is_some_and(|value| value.contains('$')))
+        .collect();
+    let mut keys: Vec<String> = pending.iter().cloned().collect();
+    // The first error of a broken config is the same on every load
+    keys.sort();
+
+    for key in keys {
+        resolve(&key, values, &mut pending, &mut Vec::new(), env, secret)?;
+    }
+    Ok(())
+}
+
+// `stack` are the settings being resolved, the one at the end refers to `key`
+fn resolve<S: BuildHasher>(
+    key: &str,
+    values: &mut HashMap<String, String, S>,
+    pending: &mut HashSet<String>,
+    stack: &mut Vec<String>,
+    env: &dyn Fn(&str) -> Option<String>,
+    secret: &dyn Fn(&str) -> bool,
+) -> Result<String, ConfigError> {
+    let error = |message: String| ConfigError::Interpolation {
+        key: stack.last().cloned().unwrap_or_else(|| key.to_string()),
+        message,
+    };
+    let Some(raw) = values.get(key).cloned() else {
+        return Err(error(format!("there is no setting {key}")));
+    };
+    if !pending.contains(key) {
+        return Ok(raw);
+    }
+    if let Some(start) = stack.iter().position(|seen| seen == key) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(key.to_string());
+        return Err(ConfigError::Cycle(cycle));
+    }
+
+    stack.push(key.to_string());
+    let is_secret = secret(key) || Secret::is_reference(&raw);
+    let mut value = String::with_capacity(raw.len());
+    let mut rest = raw.as_str();
+    while let Some(at) = rest.find('$') {
+        value.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            value.push('$');
+            rest = after;
+        } else if let Some(reference) = rest.strip_prefix("${") {
+            let Some((name, after)) = reference.split_once('}') else {
+                return Err(ConfigError::Interpolation {
+                    key: key.to_string(),
+                    message: if is_secret {
+                        "unclosed ${".to_string()
+                    } else {
+                        format!("unclosed ${{ in {raw:?}")
+                    },
+                });
+            };
+            if let Some(var) = name.strip_prefix("env:") {
+                value.push_str(&env(var).ok_or_else(|| ConfigError::Interpolation {
+                    key: key.to_string(),
+                    message: format!("the environment variable {var} is not set"),
+                })?);
+            } else {
+                let name = name.trim();
+                if let Some(message) = refuse(name, values, secret, is_secret) {
+                    return Err(ConfigError::Interpolation {
+                        key: key.to_string(),
+                        message,
+                    });
+                }
+                value.push_str(&resolve(name, values, pending, stack, env, secret)?);
+            }
+            rest = after;
+        } else {
+            value.push('$');
+            rest = &rest[1..];
+        }
+    }
+    value.push_str(rest);
+    stack.pop();
+
+    pending.remove(key);
+    values.insert(key.to_string(), value.clone());
+    Ok(value)
+}
+
+// Why `name` can't be interpolated into a setting, a secret if `into_secret`
+fn refuse<S: BuildHasher>(
+    name: &str,
+    values: &HashMap<String, String, S>,
+    secret: &dyn Fn(&str) -> bool,
+    into_secret: bool,
+) -> Option<String> {
+    if values
+        .get(name)
+        .is_some_and(|value| Secret::is_reference(value))
+    {
+        Some(format!(
+            "{name} refers to a file or variable, it can't be interpolated"
+        ))
+    } else if secret(name) && !into_secret {
+        Some(format!("{name} is a secret, only a secret can refer to it"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(pairs: &[(&str, &str)]) -> Result<HashMap<String, String>, ConfigError> {
+        let mut values: HashMap<String, String> = pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        let keys: Vec<String> = values.keys().cloned().collect();
+        let env = |var: &str| (var == "HOME").then(|| "/home/crab".to_string());
+        let secret = |key: &str| key.ends_with("password");
+        interpolate(&mut values, keys, &env, &secret).map(|()| values)
+    }
+
+    #[test]
+    fn test_references() {
+        let values = run(&[
+            ("host", "db.example"),
+            ("url", "postgres://${host}:${db.port}/app"),
+            ("db.port", "5432"),
+            ("data", "${env:HOME}/data"),
+            ("price", "$$5 or $6"),
+        ])
+        .unwrap();
+        assert_eq!(values["url"], "postgres://db.example:5432/app");
+        assert_eq!(values["data"], "/home/crab/data");
+        assert_eq!(values["price"], "$5 or $6");
+    }
+
+    #[test]
+    fn test_cycles() {
+        let result = run(&[("a", "${b}"), ("b", "x${c}"), ("c", "${a}")]);
+        assert!(matches!(result, Err(ConfigError::Cycle(cycle)) if cycle == ["a", "b", "c", "a"]));
+        assert!(matches!(run(&[("a", "${a}")]), Err(ConfigError::Cycle(_))));
+    }
+
+    #[test]
+    fn test_errors() {
+        let key_of = |pairs: &[(&str, &str)]| match run(pairs) {
+            Err(ConfigError::Interpolation { key, .. }) => key,
+            result => panic!("expected an interpolation error, got {result:?}"),
+        };
+        assert_eq!(key_of(&[("a", "${nothing}")]), "a");
+        assert_eq!(key_of(&[("a", "${b}"), ("b", "${env:UNSET}")]), "b");
+        assert_eq!(key_of(&[("a", "${open")]), "a");
+    }
+
+    #[test]
+    fn test_secrets() {
+        let values = run(&[("password", "hunter2"), ("db.password", "u:${password}")]).unwrap();
+        assert_eq!(values["db.password"], "u:hunter2");
+
+        let Err(ConfigError::Interpolation { key, message }) = run(&[
+            ("password", "hunter2"),
+            ("url", "postgres://u:${password}@h"),
+        ]) else {
+            panic!("a secret was interpolated into a setting that is none");
+        };
+        assert_eq!(key, "url");
+        assert!(!message.contains("hunter2"));
+
+        let result = run(&[("token", "@env:TOKEN"), ("password", "${token}")]);
+        assert!(matches!(result, Err(ConfigError::Interpolation { key, .. }) if key == "password"));
+        let Err(ConfigError::Interpolation { message, .. }) = run(&[("password", "${hunter2")])
+        else {
+            panic!("an unclosed ${{ was interpolated");
+        };
+        assert!(!message.contains("hunter2"));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{config::Config, custom_error::ConfigError, include};
+
+use std::{collections::HashMap, fmt, path::PathBuf};
+
+/// Where the effective value of a setting came from, see `ConfigReader::source`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A default of the layers or of the schema
+    Default,
+    File(PathBuf),
+    /// The name of the environment variable, e.g. `APP_MAX_RETRIES`
+    Env(String),
+    /// The command line flag, e.g. `--max-retries`
+    Cli(String),
+    /// Text passed to `ConfigReader::load_str`
+    Inline,
+    /// A value passed to `ConfigReader::set`
+    Set,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file {}", path.display()),
+            Source::Env(var) => write!(f, "environment variable {var}"),
+            Source::Cli(flag) => write!(f, "command line flag {flag}"),
+            Source::Inline => write!(f, "inline config"),
+            Source::Set => write!(f, "set by the program"),
+        }
+    }
+}
+
+/// The sources of a config, later layers override the earlier ones
+///
+/// ```
+/// # use config_reader::layers::Layers;
+/// let layers = Layers::new()
+///     .defaults(&[("max_retries", "3")])
+///     .optional_file("/etc/app/config.toml")
+///     .optional_file("config.toml")
+///     .env("APP")
+///     .args(["--max-retries", "5"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Layers {
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone)]
+enum Layer {
+    Defaults(HashMap<String, String>),
+    File { path: PathBuf, required: bool },
+    // (variable, key, value)
+    Env(Vec<(String, String, String)>),
+    Args(Vec<String>),
+}
+
+impl Layers {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn defaults(mut self, defaults: &[(&str, &str)]) -> Self {
+        let defaults = defaults
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        self.layers.push(Layer::Defaults(defaults));
+        self
+    }
+
+    /// A config file in the format of its extension, loading fails if it can't be read
+    #[must_use]
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layers.push(Layer::File {
+            path: path.into(),
+            required: true,
+        });
+        self
+    }
+
+    /// Like `file`, but a file that does not exist is skipped
+    #[must_use]
+    pub fn optional_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layers.push(Layer::File {
+            path: path.into(),
+            required: false,
+        });
+        self
+    }
+
+    /// The environment variables starting with `{prefix}_`, see `env_vars`
+    #[must_use]
+    pub fn env(self, prefix: &str) -> Self {
+        self.env_vars(prefix, std::env::vars())
+    }
+
+    /// `APP_MAX_RETRIES` sets `max_retries` and `APP_SERVER__PORT` sets `server.port`
+    /// for the prefix `APP`, other variables and `Config::PATH_VAR` are ignored
+    #[must_use]
+    pub fn env_vars(
+        mut self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let prefix = format!("{prefix}_");
+        let vars = vars
+            .into_iter()
+            .filter(|(var, _)| var != Config::PATH_VAR)
+            .filter_map(|(var, value)| {
+                let key = var
+                    .strip_prefix(&prefix)?
+                    .to_ascii_lowercase()
+                    .replace("__", ".");
+                (!key.is_empty()).then_some((var, key, value))
+            })
+            .collect();
+        self.layers.push(Layer::Env(vars));
+        self
+    }
+
+    /// `--max-retries 5`, `--max-retries=5` and `--server.port=80`, dashes in a flag are
+    /// underscores in its key and a flag without a value is `true`
+    #[must_use]
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.layers
+            .push(Layer::Args(args.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Merges the layers into the effective settings and where each of them came from
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Io` if a required file can't be read, `ConfigError::InvalidFormat`
+    /// if the format of a file can't be told or for an argument that is no flag,
+    /// or `ConfigError::Parse` if a file is not valid in its format
+    pub fn merge(&self) -> Result<HashMap<String, (String, Source)>, ConfigError> {
+        let mut merged = HashMap::new();
+        for layer in &self.layers {
+            match layer {
+                Layer::Defaults(defaults) => {
+                    for (key, value) in defaults {
+                        merged.insert(key.clone(), (value.clone(), Source::Default));
+                    }
+                }
+                Layer::File { path, required } => {
+                    if !required && !path.exists() {
+                        continue;
+                    }
+                    for (key, value, file) in include::read_file(path, None, false)? {
+                        merged.insert(key, (value, Source::File(file)));
+                    }
+                }
+                Layer::Env(vars) => {
+                    for (var, key, value) in vars {
+                        merged.insert(key.clone(), (value.clone(), Source::Env(var.clone())));
+                    }
+                }
+                Layer::Args(args) => {
+                    for (flag, key, value) in parse_args(args)? {
+                        merged.insert(key, (value, Source::Cli(flag)));
+                    }
+                }
+            }
+        }
+        Ok(merged)
+    }
+}
+
+// Returns (flag, key, value) in the order of the arguments
+fn parse_args(args: &[String]) -> Result<Vec<(String, String, String)>, ConfigError> {
+    let mut parsed = Vec::new();
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        let Some(name) = arg.strip_prefix("--").filter(|name| !name.is_empty()) else {
+            return Err(ConfigError::InvalidFormat(format!(
+                "{arg} is no --flag, expected --key value or --key=value"
+            )));
+        };
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => match args.next_if(|next| !next.starts_with("--")) {
+                Some(value) => (name, value.clone()),
+                None => (name, "true".to_string()),
+            },
+        };
+        parsed.push((format!("--{name}"), name.replace('-', "_"), value));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(var, value)| ((*var).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_later_layers_win() {
+        let merged = Layers::new()
+            .defaults(&[("max_retries", "3"), ("log_level", "INFO"), ("port", "80")])
+            .env_vars(
+                "APP",
+                vars(&[
+                    ("APP_MAX_RETRIES", "4"),
+                    ("APP_LOG_LEVEL", "DEBUG"),
+                    ("HOME", "/root"),
+                ]),
+            )
+            .args(["--max-retries", "5"])
+            .merge()
+            .unwrap();
+
+        assert_eq!(
+            merged["max_retries"],
+            ("5".to_string(), Source::Cli("--max-retries".to_string()))
+        );
+        assert_eq!(
+            merged["log_level"],
+            (
+                "DEBUG".to_string(),
+                Source::Env("APP_LOG_LEVEL".to_string())
+            )
+        );
+        assert_eq!(merged["port"], ("80".to_string(), Source::Default));
+        assert!(!merged.contains_key("home"));
+    }
+
+    #[test]
+    fn test_files() {
+        let path = std::env::temp_dir().join(format!("layers_{}.toml", std::process::id()));
+        fs::write(&path, "[server]\nport = 8080\n").unwrap();
+        let merged = Layers::new()
+            .optional_file("does/not/exist.toml")
+            .file(&path)
+            .env_vars("APP", vars(&[("APP_SERVER__HOST", "localhost")]))
+            .merge();
+        fs::remove_file(&path).unwrap();
+
+        let merged = merged.unwrap();
+        assert_eq!(
+            merged["server.port"],
+            ("8080".to_string(), Source::File(path))
+        );
+        assert_eq!(merged["server.host"].0, "localhost");
+        assert!(matches!(
+            Layers::new().file("does/not/exist.toml").merge(),
+            Err(ConfigError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_args() {
+        let args: Vec<String> = ["--server.port=80", "--verbose", "--log-level", "WARN"]
+            .map(String::from)
+            .to_vec();
+        let parsed = parse_args(&args).unwrap();
+        assert_eq!(
+            parsed[0],
+            ("--server.port".into(), "server.port".into(), "80".into())
+        );
+        assert_eq!(
+            parsed[1],
+            ("--verbose".into(), "verbose".into(), "true".into())
+        );
+        assert_eq!(
+            parsed[2],
+            ("--log-level".into(), "log_level".into(), "WARN".into())
+        );
+        assert!(parse_args(&["stray".to_string()]).is_err());
+    }
+}
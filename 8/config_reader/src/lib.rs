@@ -0,0 +1,17 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+pub mod cli;
+pub mod config;
+pub mod custom_error;
+pub mod de;
+pub mod diagnostic;
+pub mod document;
+pub mod format;
+pub mod include;
+pub mod interpolate;
+pub mod layers;
+pub mod reader;
+pub mod schema;
+pub mod secret;
+pub mod value;
+pub mod watch;
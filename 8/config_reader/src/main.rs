@@ -1,23 +1,138 @@
 #![warn(clippy::all, clippy::pedantic)]
-mod config;
-mod custom_error;
-mod reader;
 
-use crate::{config::Config, reader::ConfigReader};
+use config_reader::{
+    config::Config,
+    format::Format,
+    layers::Layers,
+    reader::ConfigReader,
+    schema::{Schema, SettingSpec},
+    secret::Secret,
+    value::{ByteSize, SettingType},
+};
+use serde::Deserialize;
+use std::time::Duration;
+
+// Settings the config has no field for are ignored
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+struct AppConfig {
+    max_retries: u32,
+    log_level: String,
+    #[serde(deserialize_with = "config_reader::de::duration")]
+    timeout_seconds: Duration,
+    cache_size: ByteSize,
+    db_password: Secret,
+}
 
 fn main() {
-    let config = Config::new(".\\src\\config.txt").expect("Could not open the config file");
-    let mut reader = ConfigReader::new(Some(&config)).expect("Could not initialize config reader");
+    // `./config.txt` is found unless `APP_CONFIG` or an explicit path says otherwise
+    let config = match Config::discover(None) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let schema = Schema::new()
+        .setting(
+            "max_retries",
+            SettingSpec::new(SettingType::UInt)
+                .required()
+                .range("1", "10"),
+        )
+        .setting(
+            "timeout_seconds",
+            SettingSpec::new(SettingType::Duration).default("30s"),
+        )
+        .setting(
+            "log_level",
+            SettingSpec::new(SettingType::Text).default("INFO"),
+        )
+        .setting(
+            "cache_size",
+            SettingSpec::new(SettingType::Size).default("64MiB"),
+        )
+        .setting(
+            "db_password",
+            SettingSpec::new(SettingType::Secret).required(),
+        );
+    let mut reader = ConfigReader::new(Some(&config))
+        .expect("Could not initialize config reader")
+        .with_schema(schema);
 
-    match reader.load_config() {
-        Ok(val) => println!("{val:#?}"),
+    // Later layers win, e.g. `APP_MAX_RETRIES=7 cargo run -- --log-level DEBUG`
+    let layers = Layers::new()
+        .optional_file("/etc/config_reader/config.toml")
+        .file(config.get_config_path())
+        .env("APP")
+        .args(std::env::args().skip(1));
+    // Secrets are redacted, `@file:/run/secrets/db` or `@env:DB_PASSWORD` keep them out of the file
+    match reader.load_layers(&layers) {
+        Ok(_) => println!("{:#?}", reader.redacted_values()),
         Err(e) => panic!("Encountered error while loading config: {e:#?}"),
     }
+    for key in ["max_retries", "log_level", "cache_size"] {
+        if let Some(source) = reader.source(key) {
+            println!("{key} comes from the {source}");
+        }
+    }
 
-    let some_key = String::from("too_big");
-    let value = reader.get_setting_as_u32(&some_key).unwrap();
+    let retries = reader
+        .get_setting_as_u32("max_retries")
+        .expect("FATAL ERROR: Could not load critical setting 'max_retries'");
+    println!("Max retries: {retries}");
+    if let (Ok(timeout), Ok(cache_size)) = (
+        reader.get_duration("timeout_seconds"),
+        reader.get_size("cache_size"),
+    ) {
+        println!("Timeout: {timeout:?}, cache size: {cache_size}");
+    }
 
-    println!("{some_key} : {value}");
+    match reader.deserialize::<AppConfig>() {
+        Ok(app) => println!("{app:?}"),
+        Err(e) => println!("Could not read the app config: {e}"),
+    }
+
+    match reader.get::<u16>("port") {
+        Ok(port) => println!("port : {port}"),
+        Err(e) => println!("Could not read the port: {e}"),
+    }
+
+    // A service would `spawn` the watcher to pick up edits of the file while it runs
+    let watcher = reader.watch().expect("Could not watch the config file");
+    watcher.subscribe(|diff, reader| {
+        for (key, change) in diff.iter() {
+            if reader.is_secret(key) {
+                println!("{key} changed");
+            } else {
+                println!("{key} changed: {change:?}");
+            }
+        }
+    });
+    match watcher.reload() {
+        Ok(diff) if diff.is_empty() => println!("The config file did not change"),
+        Ok(_) => {}
+        Err(e) => println!("Kept the last good config: {e:?}"),
+    }
+
+    // Every problem of a broken config at once, shown with the lines they are in
+    let mut strict = ConfigReader::new(Some(&config))
+        .expect("Could not initialize config reader")
+        .collect_errors();
+    if let Err(e) = strict.load_str("[server\nport = \"80\" 443\nmax_retries = 5", Format::Ini) {
+        println!("{e}");
+    }
+
+    // Nothing gets clamped anymore, a range turns a value that is too big into an error
+    let some_key = "too_big";
+    let too_big = SettingSpec::new(SettingType::UInt).max("100");
+    match reader
+        .get::<String>(some_key)
+        .and_then(|value| too_big.check(some_key, &value))
+    {
+        Ok(value) => println!("{some_key} : {value:?}"),
+        Err(e) => println!("Could not read {some_key}: {e}"),
+    }
 }
 
 /*
@@ -36,4 +151,4 @@ Also, I tried to reduce smell in this code, but lifetimes are needed.
 
     Custom Error Types
     Custom error type would help us to handle app specific errors, which is better then throwing just default errors
-*/
\ No newline at end of file
+*/
@@ -1,51 +1,507 @@
-use crate::{config::Config, custom_error::ConfigError};
+use crate::{
+    config::Config,
+    custom_error::ConfigError,
+    de,
+    document::{self, IniDocument},
+    format::Format,
+    include,
+    interpolate::interpolate,
+    layers::{Layers, Source},
+    schema::Schema,
+    secret::{self, Secret},
+    value::{ByteSize, FromSetting},
+    watch::ConfigWatcher,
+};
 
+use serde::de::DeserializeOwned;
 use std::{
     collections::HashMap,
-    fs,
-    io::{BufRead, BufReader, ErrorKind},
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use zeroize::Zeroize;
 
 #[allow(unused)]
+#[derive(Clone)]
 pub struct ConfigReader {
     config: Config,
     values: HashMap<String, String>,
+    schema: Option<Schema>,
+    format: Option<Format>,
+    collect_errors: bool,
+    sources: HashMap<String, Source>,
+    // Plaintext of the secrets `values` has as `secret::REDACTED`
+    secrets: HashMap<String, Secret>,
+    // Changes `save` writes to the file, in the order they were made
+    edits: Vec<Edit>,
+    // The config file and the files it includes, as of the last `load_config`
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Set(String, String),
+    Remove(String),
 }
 
 impl ConfigReader {
-    const MAX_INT_VALUE: u32 = 100;
-    pub fn get_setting_as_u32(&self, key: &str) -> Result<u32, ConfigError> {
+    /// Reads a setting as any `FromSetting` type, e.g. `reader.get::<Vec<u16>>("ports")`
+    ///
+    /// Only a `Secret` gets the plaintext of a secret, see `is_secret`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::MissingSetting` if there is no such setting
+    /// or the error of `FromSetting::read_setting`, e.g. `ConfigError::InvalidValue`
+    pub fn get<T: FromSetting>(&self, key: &str) -> Result<T, ConfigError> {
         let Some(value) = self.values.get(key) else {
             return Err(ConfigError::MissingSetting(key.to_string()));
         };
-
-        let value: u32 = match value.trim().parse() {
-            Ok(num) if num > Self::MAX_INT_VALUE => Self::MAX_INT_VALUE,
-            Ok(num) => num,
-            Err(_) => return Err(ConfigError::InvalidValue(key.to_string(), value.to_string())),
+        let value = match self.secrets.get(key) {
+            Some(secret) if T::SECRET => secret.expose(),
+            _ => value,
         };
-    
-        Ok(value)
+        T::read_setting(key, value)
+    }
+
+    /// Ranges are up to the schema, the value is never clamped
+    ///
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_setting_as_u32(&self, key: &str) -> Result<u32, ConfigError> {
+        self.get(key)
+    }
+
+    /// `true`, `yes`, `on` and `1` are true, `false`, `no`, `off` and `0` are false
+    ///
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_bool(&self, key: &str) -> Result<bool, ConfigError> {
+        self.get(key)
+    }
+
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_i64(&self, key: &str) -> Result<i64, ConfigError> {
+        self.get(key)
+    }
+
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_u64(&self, key: &str) -> Result<u64, ConfigError> {
+        self.get(key)
+    }
+
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_f64(&self, key: &str) -> Result<f64, ConfigError> {
+        self.get(key)
+    }
+
+    /// A number with a unit of `ms`, `s`, `m`, `h` or `d`, seconds without one
+    ///
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_duration(&self, key: &str) -> Result<Duration, ConfigError> {
+        self.get(key)
+    }
+
+    /// A number of bytes with an optional unit like `KB` or `MiB`
+    ///
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_size(&self, key: &str) -> Result<ByteSize, ConfigError> {
+        self.get(key)
+    }
+
+    /// A password or token, read from the file or variable the setting may refer to
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::MissingSetting` if there is no such setting,
+    /// or `ConfigError::Io` if the file or variable can't be read
+    pub fn get_secret(&self, key: &str) -> Result<Secret, ConfigError> {
+        self.get(key)
+    }
+
+    /// Reads all settings into a `T`, e.g. `let config: AppConfig = reader.deserialize()?`
+    ///
+    /// Sections are nested structs and lists are either comma separated
+    /// or sections like `servers.0`, `servers.1` ...
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Deserialize` with the key of the first setting that does not fit `T`
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        // `Secret` fields need the plaintext, the deserializer borrows it
+        let mut values = self.exposed_values();
+        let result = de::from_secret_settings(&values, |key| self.is_secret(key));
+        self.zero_secrets(&mut values);
+        result
+    }
+
+    /// Comma separated values, an empty setting is an empty list
+    ///
+    /// # Errors
+    ///
+    /// See `get`
+    pub fn get_list<T: FromSetting>(&self, key: &str) -> Result<Vec<T>, ConfigError> {
+        self.get(key)
     }
 
     // Altered the function a bit
+    /// Reads the config file in its format and checks it against the schema, if there is one
+    ///
+    /// The format is the one set with `with_format`, or else the one of the file extension.
+    /// `include = "path"` reads other files first and `${key}` or `${env:VAR}` are replaced,
+    /// see `include::INCLUDE_KEY` and `interpolate::interpolate`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Io` if the file can't be read, `ConfigError::InvalidFormat`
+    /// if the format can't be told, `ConfigError::Parse` if the file is not valid in it,
+    /// `ConfigError::Cycle` if files include each other or settings refer to each other,
+    /// `ConfigError::Interpolation` for a `${...}` that can't be resolved,
+    /// or the error of `Schema::validate`, see `collect_errors` for all of the problems
     pub fn load_config(&mut self) -> Result<&HashMap<String, String>, ConfigError> {
-        let file = fs::File::open(self.config.get_config_path())?;
+        let path = Path::new(self.config.get_config_path());
+        let (settings, files) = include::read_files(path, self.format, self.collect_errors)?;
+        self.files = files;
+
+        self.load(
+            settings
+                .into_iter()
+                .map(|(key, value, file)| (key, (value, Source::File(file)))),
+        )
+    }
+
+    /// Adds the settings in `text` to the ones loaded so far and checks them against the schema
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Parse` if `text` is not valid in `format`, the error of
+    /// `interpolate::interpolate` or the error of `Schema::validate`
+    pub fn load_str(
+        &mut self,
+        text: &str,
+        format: Format,
+    ) -> Result<&HashMap<String, String>, ConfigError> {
+        let settings = self.parse(format, text)?;
+        self.load(
+            settings
+                .into_iter()
+                .map(|(key, value)| (key, (value, Source::Inline))),
+        )
+    }
+
+    /// Replaces the settings with the merged `layers` and checks them against the schema
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `Layers::merge`, of `interpolate::interpolate` or of `Schema::validate`
+    pub fn load_layers(
+        &mut self,
+        layers: &Layers,
+    ) -> Result<&HashMap<String, String>, ConfigError> {
+        let merged = layers.merge()?;
+        self.values.clear();
+        self.sources.clear();
+        self.secrets.clear();
+        self.load(merged)
+    }
+
+    /// Every setting loaded so far, with the defaults of the schema
+    ///
+    /// Secrets are their reference or `secret::REDACTED`, see `get_secret` for the plaintext
+    #[must_use]
+    pub fn values(&self) -> &HashMap<String, String> {
+        &self.values
+    }
 
-        let buffer_reader = BufReader::new(file);
+    /// Like `values`, but every secret is `secret::REDACTED`, also the references, for showing them
+    #[must_use]
+    pub fn redacted_values(&self) -> HashMap<&str, &str> {
+        self.values
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.is_secret(key) {
+                    secret::REDACTED
+                } else {
+                    value.as_str()
+                };
+                (key.as_str(), value)
+            })
+            .collect()
+    }
 
-        for line_result in buffer_reader.lines() {
-            match line_result {
-                Ok(val) => {
-                    let values = Self::get_values(&val)?;
-                    let (key, value) = values;
-                    self.values.insert(key.to_string(), value.to_string());
+    /// Whether the schema declares `key` as a `SettingType::Secret`
+    /// or its value refers to a file or variable, see `Secret::is_reference`
+    #[must_use]
+    pub fn is_secret(&self, key: &str) -> bool {
+        self.secrets.contains_key(key)
+            || self
+                .values
+                .get(key)
+                .is_some_and(|value| Secret::is_reference(value))
+            || self
+                .schema
+                .as_ref()
+                .is_some_and(|schema| schema.is_secret(key))
+    }
+
+    #[must_use]
+    pub fn config_path(&self) -> &str {
+        self.config.get_config_path()
+    }
+
+    // The files `ConfigWatcher` checks for changes
+    pub(crate) fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Loads the config file and reloads it whenever it changes, see `ConfigWatcher`
+    ///
+    /// The watcher loads with the format and schema of this reader, not its settings
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `load_config`
+    pub fn watch(&self) -> Result<ConfigWatcher, ConfigError> {
+        ConfigWatcher::new(Self {
+            values: HashMap::with_capacity(Self::MAX_VALUES),
+            sources: HashMap::new(),
+            secrets: HashMap::new(),
+            edits: Vec::new(),
+            files: Vec::new(),
+            ..self.clone()
+        })
+    }
+
+    /// Changes `key` to `value` if the settings still fit the schema, see `save`
+    ///
+    /// `${key}` and `${env:VAR}` in `value` are replaced, the file keeps them
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `interpolate::interpolate` or of `Schema::validate`,
+    /// the settings are unchanged then
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let mut values = self.exposed_values();
+        values.insert(key.to_string(), value.to_string());
+        let set = interpolate(
+            &mut values,
+            [key.to_string()],
+            &|var| env::var(var).ok(),
+            &|key| self.is_secret(key),
+        )
+        .and_then(|()| self.check(&mut values));
+        if let Err(e) = set {
+            self.zero_secrets(&mut values);
+            return Err(e);
+        }
+        self.values = values;
+        self.hide_secrets();
+
+        self.sources.insert(key.to_string(), Source::Set);
+        self.edits
+            .push(Edit::Set(key.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    /// Removes `key` if the settings still fit the schema and returns its value, see `save`
+    ///
+    /// A setting the schema has a default for goes back to the default
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Defaulted` if the value is a default,
+    /// `ConfigError::NotInConfigFile` if the value comes from somewhere else `save` doesn't
+    /// write to, like an included file or a variable, or the error of `Schema::validate`,
+    /// e.g. for a required setting
+    pub fn remove(&mut self, key: &str) -> Result<Option<String>, ConfigError> {
+        let mut values = self.values.clone();
+        let removed = values.remove(key);
+        if removed.is_none() {
+            return Ok(None);
+        }
+        match self.sources.get(key) {
+            None | Some(Source::Set) => {}
+            Some(Source::Default) => return Err(ConfigError::Defaulted(key.to_string())),
+            Some(Source::File(file)) if file == Path::new(self.config.get_config_path()) => {}
+            Some(source) => {
+                return Err(ConfigError::NotInConfigFile {
+                    key: key.to_string(),
+                    source: source.clone(),
+                });
+            }
+        }
+        self.check(&mut values)?;
+        self.values = values;
+        self.hide_secrets();
+
+        self.sources.remove(key);
+        if self.values.contains_key(key) {
+            self.sources.insert(key.to_string(), Source::Default);
+        }
+        self.edits.push(Edit::Remove(key.to_string()));
+        Ok(removed)
+    }
+
+    /// Writes the settings changed with `set` and `remove` to the config file
+    ///
+    /// Every other line keeps its place, comments and spacing, and the file is replaced
+    /// atomically, see `document::write_atomically`. Only INI files can be written back
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidFormat` if the file is not INI,
+    /// or `ConfigError::Io` if it can't be read or written
+    pub fn save(&mut self) -> Result<(), ConfigError> {
+        if self.edits.is_empty() {
+            return Ok(());
+        }
+        let path = Path::new(self.config.get_config_path());
+        let format = self.format.or_else(|| Format::from_path(path));
+        if format != Some(Format::Ini) {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Can't write {} back, only INI files keep their comments",
+                path.display()
+            )));
+        }
+
+        let mut document = IniDocument::parse(&fs::read_to_string(path)?);
+        for edit in &self.edits {
+            match edit {
+                Edit::Set(key, value) => document.set(key, value),
+                Edit::Remove(key) => {
+                    document.remove(key);
                 }
-                Err(e) => {
-                    return Err(ConfigError::Io(std::io::Error::new(
-                        ErrorKind::InvalidData,
-                        e,
-                    )));
+            }
+        }
+        document::write_atomically(path, &document.to_string())?;
+        self.edits.clear();
+        Ok(())
+    }
+
+    // Checks `values` against the schema and fills in its defaults
+    fn check(&self, values: &mut HashMap<String, String>) -> Result<(), ConfigError> {
+        if let Some(schema) = &self.schema {
+            if self.collect_errors {
+                let mut errors = schema.validate_all(values);
+                match errors.len() {
+                    0 => {}
+                    1 => return Err(errors.remove(0)),
+                    _ => return Err(ConfigError::Many(errors)),
+                }
+            } else {
+                schema.validate(values)?;
+            }
+        }
+        Ok(())
+    }
+
+    // A copy of `values` with the plaintext of the secrets, see `zero_secrets`
+    fn exposed_values(&self) -> HashMap<String, String> {
+        let mut values = self.values.clone();
+        for (key, secret) in &self.secrets {
+            values.insert(key.clone(), secret.expose().to_string());
+        }
+        values
+    }
+
+    // Zeroes the plaintext `exposed_values` put in `values`
+    fn zero_secrets(&self, values: &mut HashMap<String, String>) {
+        for key in self.secrets.keys() {
+            if let Some(value) = values.get_mut(key) {
+                value.zeroize();
+            }
+        }
+    }
+
+    // Moves the plaintext of the secrets out of `values`, they keep `secret::REDACTED`
+    fn hide_secrets(&mut self) {
+        let exposed: Vec<String> = self
+            .values
+            .iter()
+            .filter(|(key, value)| {
+                *value != secret::REDACTED && !Secret::is_reference(value) && self.is_secret(key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in exposed {
+            if let Some(value) = self
+                .values
+                .insert(key.clone(), secret::REDACTED.to_string())
+            {
+                self.secrets.insert(key, Secret::new(value));
+            }
+        }
+        // Secrets that were removed or replaced by a reference
+        let values = &self.values;
+        self.secrets.retain(|key, _| {
+            values
+                .get(key)
+                .is_some_and(|value| value == secret::REDACTED)
+        });
+    }
+
+    fn parse(&self, format: Format, text: &str) -> Result<HashMap<String, String>, ConfigError> {
+        if self.collect_errors {
+            format.parse_all(text)
+        } else {
+            format.parse(text)
+        }
+    }
+
+    /// Which file, variable or flag supplied the value of `key`
+    #[must_use]
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+
+    // Defaults the schema fills in are marked as such. The secrets are hidden
+    // after interpolating, so a secret loaded before can be part of a new one
+    fn load(
+        &mut self,
+        settings: impl IntoIterator<Item = (String, (String, Source))>,
+    ) -> Result<&HashMap<String, String>, ConfigError> {
+        for (key, secret) in &self.secrets {
+            self.values.insert(key.clone(), secret.expose().to_string());
+        }
+        let mut loaded = Vec::new();
+        for (key, (value, source)) in settings {
+            self.values.insert(key.clone(), value);
+            self.sources.insert(key.clone(), source);
+            loaded.push(key);
+        }
+        let (schema, secrets) = (&self.schema, &self.secrets);
+        let interpolated = interpolate(
+            &mut self.values,
+            loaded,
+            &|var| env::var(var).ok(),
+            &|key| secrets.contains_key(key) || schema.as_ref().is_some_and(|s| s.is_secret(key)),
+        );
+        // Hidden even if the settings are no good, they stay in memory all the same
+        self.hide_secrets();
+        interpolated?;
+        if self.schema.is_some() {
+            let mut values = std::mem::take(&mut self.values);
+            let checked = self.check(&mut values);
+            self.values = values;
+            self.hide_secrets();
+            checked?;
+            for key in self.values.keys() {
+                if !self.sources.contains_key(key) {
+                    self.sources.insert(key.clone(), Source::Default);
                 }
             }
         }
@@ -56,6 +512,9 @@ impl ConfigReader {
 impl ConfigReader {
     const MAX_VALUES: usize = 256;
 
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Io` if there is no config
     pub fn new(config: Option<&Config>) -> Result<Self, ConfigError> {
         let config = if let Some(val) = config {
             val.clone()
@@ -69,32 +528,183 @@ impl ConfigReader {
         Ok(Self {
             config,
             values: HashMap::with_capacity(Self::MAX_VALUES),
+            schema: None,
+            format: None,
+            collect_errors: false,
+            sources: HashMap::new(),
+            secrets: HashMap::new(),
+            edits: Vec::new(),
+            files: Vec::new(),
         })
     }
 
-    // contains exactly 1 =
-    fn get_values(val: &str) -> Result<(&str, &str), ConfigError> {
-        let sanitized_val = val.trim();
-        let mut values: Vec<&str> = sanitized_val.split('=').collect();
+    /// Reads the config file as `format` whatever its extension
+    #[must_use]
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
 
-        match values.len() {
-            2 => {
-                // To put it into a helper method, lifetimes needed :(
-                let Some(value) = values.pop() else {
-                    return Err(ConfigError::InvalidValue(
-                        val.to_string(),
-                        "Not available".to_string(),
-                    ));
-                };
-                let Some(key) = values.pop() else {
-                    return Err(ConfigError::InvalidValue(
-                        val.to_string(),
-                        "Not available".to_string(),
-                    ));
-                };
-                Ok((key, value))
-            }
-            _ => Err(ConfigError::InvalidFormat(val.to_string())),
-        }
+    /// Loading reports every broken line and every setting that does not fit the schema
+    /// instead of stopping at the first problem, several problems are a `ConfigError::Many`
+    #[must_use]
+    pub fn collect_errors(mut self) -> Self {
+        self.collect_errors = true;
+        self
+    }
+
+    /// `load_config` checks the settings against `schema` and fills in its defaults
+    #[must_use]
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{schema::SettingSpec, value::SettingType};
+
+    #[test]
+    fn test_set_remove_and_save() {
+        let path = std::env::temp_dir().join(format!("save_{}.ini", std::process::id()));
+        fs::write(
+            &path,
+            "# retries\nmax_retries = 3 ; at most 10\nname = app\n",
+        )
+        .unwrap();
+        let config = Config::new(path.to_str().unwrap()).unwrap();
+        let schema = Schema::new().setting(
+            "max_retries",
+            SettingSpec::new(SettingType::UInt).range("1", "10"),
+        );
+        let mut reader = ConfigReader::new(Some(&config))
+            .unwrap()
+            .with_schema(schema);
+        reader.load_config().unwrap();
+
+        assert!(matches!(
+            reader.set("max_retries", "11"),
+            Err(ConfigError::OutOfRange { .. })
+        ));
+        reader.set("max_retries", "5").unwrap();
+        reader.set("server.port", "8080").unwrap();
+        assert_eq!(reader.remove("name").unwrap().as_deref(), Some("app"));
+        assert_eq!(reader.get_setting_as_u32("max_retries").unwrap(), 5);
+        assert_eq!(reader.source("server.port"), Some(&Source::Set));
+        reader.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            saved,
+            "# retries\nmax_retries = 5 ; at most 10\n\n[server]\nport = 8080\n"
+        );
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Db {
+        db_password: Secret,
+        db_user: String,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(unused)]
+    struct Api {
+        api_token: u64,
+    }
+
+    #[test]
+    fn test_secrets_stay_hidden() {
+        let path = std::env::temp_dir().join(format!("secrets_{}.ini", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let config = Config::new(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        let schema = Schema::new()
+            .setting("db_password", SettingSpec::new(SettingType::Secret))
+            // A range would have to show the secret in its error, it is not checked
+            .setting(
+                "api_token",
+                SettingSpec::new(SettingType::Secret).range("a", "b"),
+            );
+        let mut reader = ConfigReader::new(Some(&config))
+            .unwrap()
+            .with_schema(schema);
+        reader
+            .load_str(
+                "db_password = hunter2\ndb_user = app\napi_token = t0ken\nkey = @env:CONFIG_READER_UNSET",
+                Format::Ini,
+            )
+            .unwrap();
+
+        assert!(!format!("{:?}", reader.values()).contains("hunter2"));
+        assert_eq!(
+            reader.get::<String>("db_password").unwrap(),
+            secret::REDACTED
+        );
+        assert_eq!(
+            reader.get_secret("db_password").unwrap().expose(),
+            "hunter2"
+        );
+        let db: Db = reader.deserialize().unwrap();
+        assert_eq!(
+            (db.db_password.expose(), db.db_user.as_str()),
+            ("hunter2", "app")
+        );
+        let error = reader.deserialize::<Api>().unwrap_err().to_string();
+        assert_eq!(error, "api_token: expected u64, found [REDACTED]");
+
+        // A reference is a secret without a schema, and says why it can't be read
+        assert!(reader.is_secret("key"));
+        let error = reader.get::<Secret>("key").unwrap_err();
+        assert!(error.to_string().contains("CONFIG_READER_UNSET"));
+
+        reader.set("db_password", "s3cr3t").unwrap();
+        assert_eq!(reader.values()["db_password"], secret::REDACTED);
+        assert_eq!(
+            reader.get::<Secret>("db_password").unwrap().expose(),
+            "s3cr3t"
+        );
+
+        // A secret is only interpolated into another secret, never into a setting anyone can see
+        let error = reader
+            .set("url", "postgres://app:${db_password}@db")
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::Interpolation { ref key, .. } if key == "url"));
+        assert!(!error.to_string().contains("s3cr3t") && !reader.values().contains_key("url"));
+        reader.set("api_token", "${db_password}!").unwrap();
+        assert_eq!(reader.get_secret("api_token").unwrap().expose(), "s3cr3t!");
+        assert!(reader.set("db_password", "${key}").is_err());
+    }
+
+    // Saving would leave the included setting in place, it would be back on the next load
+    #[test]
+    fn test_remove_included_setting() {
+        let dir = std::env::temp_dir().join(format!("remove_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.ini");
+        fs::write(dir.join("base.ini"), "name = base\n").unwrap();
+        fs::write(&path, "include = base.ini\nport = 8080\n").unwrap();
+        let config = Config::new(path.to_str().unwrap()).unwrap();
+        let schema = Schema::new().setting(
+            "timeout",
+            SettingSpec::new(SettingType::Duration).default("30s"),
+        );
+        let mut reader = ConfigReader::new(Some(&config))
+            .unwrap()
+            .with_schema(schema);
+        reader.load_config().unwrap();
+
+        let defaulted = reader.remove("timeout").unwrap_err();
+        assert!(matches!(defaulted, ConfigError::Defaulted(ref key) if key == "timeout"));
+        let error = reader.remove("name").unwrap_err();
+        assert_eq!(reader.get::<String>("name").unwrap(), "base");
+        assert_eq!(reader.remove("port").unwrap().as_deref(), Some("8080"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            error,
+            ConfigError::NotInConfigFile { ref key, source: Source::File(_) } if key == "name"
+        ));
     }
 }
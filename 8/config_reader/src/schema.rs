@@ -0,0 +1,395 @@
+use crate::custom_error::ConfigError;
+use crate::include;
+use crate::secret::Secret;
+use crate::value::{FromSetting, SettingType, Value};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// The settings a config is expected to have
+///
+/// ```
+/// # use config_reader::{schema::{Schema, SettingSpec}, value::SettingType};
+/// let schema = Schema::new()
+///     .setting("max_retries", SettingSpec::new(SettingType::UInt).required().range("1", "10"))
+///     .setting("timeout", SettingSpec::new(SettingType::Duration).default("30s"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    settings: BTreeMap<String, SettingSpec>,
+    deny_unknown: bool,
+}
+
+impl Schema {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn setting(mut self, key: &str, spec: SettingSpec) -> Self {
+        self.settings.insert(key.to_string(), spec);
+        self
+    }
+
+    /// Reads a schema from a config file in any `Format`, every setting in it is
+    /// the spec of the setting with the same key, see `SettingSpec::parse`
+    ///
+    /// ```ini
+    /// max_retries = uint required min=1 max=10
+    /// [server]
+    /// timeout = duration default=30s
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidSchema` for a spec that can't be read,
+    /// or the error of reading the file, see `ConfigReader::load_config`
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let mut schema = Self::new();
+        for (key, spec, _) in include::read_file(path, None, false)? {
+            let spec = SettingSpec::parse(&spec)
+                .map_err(|e| ConfigError::InvalidSchema(format!("{key}: {e}")))?;
+            schema = schema.setting(&key, spec);
+        }
+        Ok(schema)
+    }
+
+    /// Settings the schema does not declare fail the validation, they are kept otherwise
+    #[must_use]
+    pub fn deny_unknown(mut self) -> Self {
+        self.deny_unknown = true;
+        self
+    }
+
+    #[must_use]
+    pub fn spec(&self, key: &str) -> Option<&SettingSpec> {
+        self.settings.get(key)
+    }
+
+    /// Whether `key` is declared as a `SettingType::Secret`
+    #[must_use]
+    pub fn is_secret(&self, key: &str) -> bool {
+        self.spec(key)
+            .is_some_and(|spec| *spec.setting_type() == SettingType::Secret)
+    }
+
+    /// Checks every setting against its spec and adds the defaults of the missing ones
+    ///
+    /// # Errors
+    ///
+    /// Returns the first of:
+    /// - `ConfigError::UnknownSetting` if unknown settings are denied and there is one
+    /// - `ConfigError::MissingSetting` if a required setting is missing
+    /// - `ConfigError::InvalidValue` if a setting is not of its declared type
+    /// - `ConfigError::Io` if a secret refers to a file or variable that can't be read
+    /// - `ConfigError::OutOfRange` if a setting is not within its range
+    /// - `ConfigError::InvalidSchema` if a default or a bound is not of the declared type
+    pub fn validate(&self, values: &mut HashMap<String, String>) -> Result<(), ConfigError> {
+        match self.check_all(values, false).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `validate`, but returns every problem instead of the first one
+    #[must_use]
+    pub fn validate_all(&self, values: &mut HashMap<String, String>) -> Vec<ConfigError> {
+        self.check_all(values, true)
+    }
+
+    fn check_all(
+        &self,
+        values: &mut HashMap<String, String>,
+        collect_all: bool,
+    ) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        if self.deny_unknown {
+            let mut unknown: Vec<&String> = values
+                .keys()
+                .filter(|key| !self.settings.contains_key(*key))
+                .collect();
+            unknown.sort();
+            errors.extend(
+                unknown
+                    .into_iter()
+                    .map(|key| ConfigError::UnknownSetting(key.clone())),
+            );
+            if !collect_all && !errors.is_empty() {
+                return errors;
+            }
+        }
+
+        for (key, spec) in &self.settings {
+            if let Err(error) = Self::check_setting(key, spec, values) {
+                errors.push(error);
+                if !collect_all {
+                    break;
+                }
+            }
+        }
+        errors
+    }
+
+    fn check_setting(
+        key: &str,
+        spec: &SettingSpec,
+        values: &mut HashMap<String, String>,
+    ) -> Result<(), ConfigError> {
+        match values.get(key) {
+            Some(raw) => {
+                spec.check(key, raw)?;
+            }
+            None => match &spec.default {
+                Some(default) => {
+                    spec.check(key, default).map_err(|e| {
+                        ConfigError::InvalidSchema(format!("default of {key}: {e}"))
+                    })?;
+                    values.insert(key.to_string(), default.clone());
+                }
+                None if spec.required => {
+                    return Err(ConfigError::MissingSetting(key.to_string()));
+                }
+                None => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Type, default and allowed range of a setting
+///
+/// Defaults and bounds are written like the values in a config file, e.g. `"5m"` for a duration
+#[derive(Debug, Clone)]
+pub struct SettingSpec {
+    setting_type: SettingType,
+    default: Option<String>,
+    min: Option<String>,
+    max: Option<String>,
+    required: bool,
+}
+
+impl SettingSpec {
+    #[must_use]
+    pub fn new(setting_type: SettingType) -> Self {
+        Self {
+            setting_type,
+            default: None,
+            min: None,
+            max: None,
+            required: false,
+        }
+    }
+
+    #[must_use]
+    pub fn default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    /// A type followed by any of `required`, `default=VALUE`, `min=VALUE` and `max=VALUE`,
+    /// e.g. `duration default=30s max=5m`, see `SettingType::from_str` for the types
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the word that is no type or option
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut words = spec.split_whitespace();
+        let Some(setting_type) = words.next() else {
+            return Err("the spec has no type".to_string());
+        };
+        let mut parsed = Self::new(setting_type.parse()?);
+        for word in words {
+            parsed = match word.split_once('=') {
+                None if word == "required" => parsed.required(),
+                Some(("default", default)) => parsed.default(default),
+                Some(("min", min)) => parsed.min(min),
+                Some(("max", max)) => parsed.max(max),
+                _ => return Err(format!("{word} is not an option of a setting")),
+            };
+        }
+        Ok(parsed)
+    }
+
+    /// The setting has to be in the config, unless it has a default
+    #[must_use]
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Inclusive bounds, lists check every item against them
+    #[must_use]
+    pub fn range(self, min: &str, max: &str) -> Self {
+        self.min(min).max(max)
+    }
+
+    #[must_use]
+    pub fn min(mut self, min: &str) -> Self {
+        self.min = Some(min.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn max(mut self, max: &str) -> Self {
+        self.max = Some(max.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn setting_type(&self) -> &SettingType {
+        &self.setting_type
+    }
+
+    /// Parses `raw` as the declared type and checks its range
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` if `raw` is not of the declared type,
+    /// `ConfigError::Io` if it is a secret that can't be read,
+    /// `ConfigError::OutOfRange` if it is not within the range
+    /// or `ConfigError::InvalidSchema` if a bound is not of the declared type
+    pub fn check(&self, key: &str, raw: &str) -> Result<Value, ConfigError> {
+        // Only whether a secret can be read, an error must not show it and it has no range
+        if self.setting_type == SettingType::Secret {
+            return Secret::read_setting(key, raw).map(|_| Value::Secret);
+        }
+        let value = Value::parse(&self.setting_type, raw)
+            .ok_or_else(|| ConfigError::InvalidValue(key.to_string(), raw.to_string()))?;
+
+        let min = self.bound(key, self.min.as_deref())?;
+        let max = self.bound(key, self.max.as_deref())?;
+        if !in_range(&value, min.as_ref(), max.as_ref()) {
+            return Err(ConfigError::OutOfRange {
+                key: key.to_string(),
+                value: raw.to_string(),
+                min: self.min.clone(),
+                max: self.max.clone(),
+            });
+        }
+        Ok(value)
+    }
+
+    // Bounds of a list are bounds of its items
+    fn bound(&self, key: &str, bound: Option<&str>) -> Result<Option<Value>, ConfigError> {
+        let Some(bound) = bound else {
+            return Ok(None);
+        };
+        let setting_type = match &self.setting_type {
+            SettingType::List(item) => item,
+            setting_type => setting_type,
+        };
+        Value::parse(setting_type, bound).map(Some).ok_or_else(|| {
+            ConfigError::InvalidSchema(format!("bound {bound} of {key} is not a {setting_type}"))
+        })
+    }
+}
+
+fn in_range(value: &Value, min: Option<&Value>, max: Option<&Value>) -> bool {
+    match value {
+        Value::List(items) => items.iter().all(|item| in_range(item, min, max)),
+        value => min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .setting(
+                "max_retries",
+                SettingSpec::new(SettingType::UInt)
+                    .required()
+                    .range("1", "10"),
+            )
+            .setting(
+                "timeout",
+                SettingSpec::new(SettingType::Duration)
+                    .default("30s")
+                    .max("5m"),
+            )
+            .setting(
+                "ports",
+                SettingSpec::new(SettingType::List(Box::new(SettingType::UInt))).min("1"),
+            )
+    }
+
+    fn settings(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_adds_defaults() {
+        let mut values = settings(&[("max_retries", "3"), ("log_level", "INFO")]);
+        schema().validate(&mut values).unwrap();
+        assert_eq!(values["timeout"], "30s");
+        assert_eq!(values["log_level"], "INFO");
+    }
+
+    #[test]
+    fn test_out_of_range_is_an_error() {
+        let mut values = settings(&[("max_retries", "1012")]);
+        let result = schema().validate(&mut values);
+        assert!(matches!(
+            result,
+            Err(ConfigError::OutOfRange { key, max: Some(max), .. }) if key == "max_retries" && max == "10"
+        ));
+
+        let mut values = settings(&[("max_retries", "1"), ("ports", "80,0")]);
+        assert!(matches!(
+            schema().validate(&mut values),
+            Err(ConfigError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_and_unknown() {
+        let result = schema().validate(&mut HashMap::new());
+        assert!(matches!(result, Err(ConfigError::MissingSetting(key)) if key == "max_retries"));
+
+        let mut values = settings(&[("max_retries", "3"), ("port", "80")]);
+        let result = schema().deny_unknown().validate(&mut values);
+        assert!(matches!(result, Err(ConfigError::UnknownSetting(key)) if key == "port"));
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let mut values = settings(&[("timeout", "10m"), ("ports", "0"), ("port", "80")]);
+        let errors = schema().deny_unknown().validate_all(&mut values);
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                ConfigError::UnknownSetting(_),
+                ConfigError::MissingSetting(_),
+                ConfigError::OutOfRange { .. },
+                ConfigError::OutOfRange { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_parse_specs() {
+        let spec = SettingSpec::parse("list:uint required min=1 max=65535").unwrap();
+        assert_eq!(
+            *spec.setting_type(),
+            SettingType::List(Box::new(SettingType::UInt))
+        );
+        assert!(spec.check("ports", "80,443").is_ok());
+        assert!(spec.check("ports", "0").is_err());
+        assert!(SettingSpec::parse("number").is_err());
+        assert!(SettingSpec::parse("int optional").is_err());
+        assert!(SettingSpec::parse("").is_err());
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let mut values = settings(&[("max_retries", "3"), ("timeout", "soon")]);
+        let result = schema().validate(&mut values);
+        assert!(matches!(result, Err(ConfigError::InvalidValue(key, _)) if key == "timeout"));
+    }
+}
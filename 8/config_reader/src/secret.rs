@@ -0,0 +1,125 @@
+use std::{env, fmt, fs, io};
+use zeroize::Zeroize;
+
+/// What a secret shows instead of its value
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A password, token or key that never shows up in `Debug` or `Display` output
+/// and is zeroed in memory when dropped
+///
+/// Declare a setting as `SettingType::Secret` so `ConfigReader` keeps its plaintext to itself,
+/// or better refer to the plaintext instead of putting it in the config:
+/// - `@file:/run/secrets/db` is the content of the file, without the line break at the end
+/// - `@env:DB_PASSWORD` is the value of the environment variable
+///
+/// Secrets can't be compared or ordered, so nothing can find out the plaintext bit by bit
+///
+/// ```
+/// # use config_reader::secret::Secret;
+/// let password = Secret::resolve("hunter2").unwrap();
+/// assert_eq!(format!("{password} {password:?}"), "[REDACTED] Secret([REDACTED])");
+/// assert_eq!(password.expose(), "hunter2");
+/// ```
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    #[must_use]
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Reads the file or variable `raw` refers to, anything else is the secret itself
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of reading the file, or `io::ErrorKind::NotFound`
+    /// if the environment variable is not set
+    pub fn resolve(raw: &str) -> io::Result<Self> {
+        Self::resolve_with(raw, &|var| env::var(var).ok())
+    }
+
+    // `env` looks up the environment variables
+    fn resolve_with(raw: &str, env: &dyn Fn(&str) -> Option<String>) -> io::Result<Self> {
+        if let Some(path) = raw.strip_prefix("@file:") {
+            let mut contents = fs::read_to_string(path.trim())
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.trim())))?;
+            let len = contents.trim_end_matches(['\r', '\n']).len();
+            contents.truncate(len);
+            return Ok(Self(contents));
+        }
+        if let Some(var) = raw.strip_prefix("@env:") {
+            let var = var.trim();
+            return env(var).map(Self).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("the environment variable {var} is not set"),
+                )
+            });
+        }
+        Ok(Self(raw.to_string()))
+    }
+
+    /// Whether `raw` refers to a file or a variable instead of being the secret itself
+    #[must_use]
+    pub fn is_reference(raw: &str) -> bool {
+        let raw = raw.trim();
+        raw.starts_with("@file:") || raw.starts_with("@env:")
+    }
+
+    /// The plaintext, keep it out of logs
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{REDACTED}")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("hunter2");
+        assert_eq!(format!("{secret}"), REDACTED);
+        assert!(!format!("{:?}", Some(&secret)).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_references() {
+        let path = std::env::temp_dir().join(format!("secret_{}", std::process::id()));
+        fs::write(&path, "s3cr3t\n").unwrap();
+        let from_file = Secret::resolve(&format!("@file:{}", path.display()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(from_file.unwrap().expose(), "s3cr3t");
+
+        let env = |var: &str| (var == "DB_PASSWORD").then(|| "t0ken".to_string());
+        assert_eq!(
+            Secret::resolve_with("@env:DB_PASSWORD", &env)
+                .unwrap()
+                .expose(),
+            "t0ken"
+        );
+        assert!(Secret::resolve_with("@env:UNSET", &env).is_err());
+        assert!(Secret::resolve("@file:does/not/exist").is_err());
+        assert!(Secret::is_reference(" @env:DB_PASSWORD") && !Secret::is_reference("hunter2"));
+    }
+}
@@ -0,0 +1,311 @@
+use crate::{custom_error::ConfigError, secret::Secret};
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The types a setting can be declared with in a `Schema`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingType {
+    Bool,
+    Int,
+    UInt,
+    Float,
+    /// A number with a unit of `ms`, `s`, `m`, `h` or `d`, seconds without one
+    Duration,
+    /// A number of bytes with an optional unit like `KB` or `MiB`
+    Size,
+    Text,
+    /// Text that is redacted when shown, or a reference to it, see `Secret`
+    Secret,
+    /// Comma separated values of a single type
+    List(Box<SettingType>),
+}
+
+impl fmt::Display for SettingType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingType::Bool => write!(f, "bool"),
+            SettingType::Int => write!(f, "integer"),
+            SettingType::UInt => write!(f, "unsigned integer"),
+            SettingType::Float => write!(f, "float"),
+            SettingType::Duration => write!(f, "duration"),
+            SettingType::Size => write!(f, "size"),
+            SettingType::Text => write!(f, "text"),
+            SettingType::Secret => write!(f, "secret"),
+            SettingType::List(item) => write!(f, "list of {item}"),
+        }
+    }
+}
+
+/// `bool`, `int`, `uint`, `float`, `duration`, `size`, `text`, `secret`
+/// or `list:TYPE` for a list of one of them, the way schema files declare types
+impl FromStr for SettingType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if let Some(item) = name.strip_prefix("list:") {
+            return item.parse().map(|item| SettingType::List(Box::new(item)));
+        }
+        match name {
+            "bool" => Ok(SettingType::Bool),
+            "int" => Ok(SettingType::Int),
+            "uint" => Ok(SettingType::UInt),
+            "float" => Ok(SettingType::Float),
+            "duration" => Ok(SettingType::Duration),
+            "size" => Ok(SettingType::Size),
+            "text" => Ok(SettingType::Text),
+            "secret" => Ok(SettingType::Secret),
+            _ => Err(format!("{name} is not a setting type")),
+        }
+    }
+}
+
+/// A setting parsed as the type its `Schema` declares
+///
+/// Values of the same type compare by their value, that's what ranges are checked with
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Duration(Duration),
+    Size(ByteSize),
+    Text(String),
+    /// A secret that can be read, its plaintext is not kept
+    Secret,
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Parses `raw` as a `setting_type`, `None` if it isn't one
+    #[must_use]
+    pub fn parse(setting_type: &SettingType, raw: &str) -> Option<Self> {
+        match setting_type {
+            SettingType::Bool => bool::from_setting(raw).map(Value::Bool),
+            SettingType::Int => i64::from_setting(raw).map(Value::Int),
+            SettingType::UInt => u64::from_setting(raw).map(Value::UInt),
+            SettingType::Float => f64::from_setting(raw).map(Value::Float),
+            SettingType::Duration => Duration::from_setting(raw).map(Value::Duration),
+            SettingType::Size => ByteSize::from_setting(raw).map(Value::Size),
+            SettingType::Text => String::from_setting(raw).map(Value::Text),
+            SettingType::Secret => Secret::from_setting(raw).map(|_| Value::Secret),
+            SettingType::List(item) => list_items(raw)
+                .map(|raw| Value::parse(item, raw))
+                .collect::<Option<_>>()
+                .map(Value::List),
+        }
+    }
+}
+
+/// An amount of bytes, read from settings like `512`, `10KB` or `4MiB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    #[must_use]
+    pub fn new(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}B", self.0)
+    }
+}
+
+/// Types a setting can be read as, see `ConfigReader::get`
+pub trait FromSetting: Sized {
+    /// Gets the plaintext of a secret setting, every other type gets its reference
+    /// or `secret::REDACTED`, see `ConfigReader::get`
+    const SECRET: bool = false;
+
+    /// Parses the raw value of a setting, `None` if it is not a `Self`
+    fn from_setting(raw: &str) -> Option<Self>;
+
+    /// Parses the raw value of `key` and tells why it is not a `Self`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` unless the type knows better
+    fn read_setting(key: &str, raw: &str) -> Result<Self, ConfigError> {
+        Self::from_setting(raw)
+            .ok_or_else(|| ConfigError::InvalidValue(key.to_string(), raw.to_string()))
+    }
+}
+
+impl FromSetting for bool {
+    fn from_setting(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! from_str_setting {
+    ($($t:ty),*) => {$(
+        impl FromSetting for $t {
+            fn from_setting(raw: &str) -> Option<Self> {
+                raw.trim().parse().ok()
+            }
+        }
+    )*};
+}
+
+from_str_setting!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! float_setting {
+    ($($t:ty),*) => {$(
+        impl FromSetting for $t {
+            // inf and NaN are no sensible settings
+            fn from_setting(raw: &str) -> Option<Self> {
+                raw.trim().parse().ok().filter(|value: &$t| value.is_finite())
+            }
+        }
+    )*};
+}
+
+float_setting!(f32, f64);
+
+impl FromSetting for String {
+    fn from_setting(raw: &str) -> Option<Self> {
+        Some(raw.trim().to_string())
+    }
+}
+
+/// A file or variable the setting refers to that can't be read is `None`
+impl FromSetting for Secret {
+    const SECRET: bool = true;
+
+    fn from_setting(raw: &str) -> Option<Self> {
+        Secret::resolve(raw.trim()).ok()
+    }
+
+    // Says what could not be read, the plaintext itself never fails
+    fn read_setting(key: &str, raw: &str) -> Result<Self, ConfigError> {
+        Secret::resolve(raw.trim())
+            .map_err(|e| ConfigError::Io(io::Error::new(e.kind(), format!("{key}: {e}"))))
+    }
+}
+
+impl FromSetting for Duration {
+    fn from_setting(raw: &str) -> Option<Self> {
+        let (number, unit) = split_unit(raw);
+        let seconds_per_unit = match unit {
+            "ms" => 0.001,
+            "" | "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return None,
+        };
+        let number: f64 = number.parse().ok()?;
+        Duration::try_from_secs_f64(number * seconds_per_unit).ok()
+    }
+}
+
+impl FromSetting for ByteSize {
+    fn from_setting(raw: &str) -> Option<Self> {
+        let (number, unit) = split_unit(raw);
+        let bytes_per_unit: u64 = match unit.to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "kb" => 1000,
+            "kib" => 1 << 10,
+            "mb" => 1000 * 1000,
+            "mib" => 1 << 20,
+            "gb" => 1000 * 1000 * 1000,
+            "gib" => 1 << 30,
+            "tb" => 1000 * 1000 * 1000 * 1000,
+            "tib" => 1 << 40,
+            _ => return None,
+        };
+        let number: u64 = number.parse().ok()?;
+        number.checked_mul(bytes_per_unit).map(ByteSize)
+    }
+}
+
+impl<T: FromSetting> FromSetting for Vec<T> {
+    fn from_setting(raw: &str) -> Option<Self> {
+        list_items(raw).map(T::from_setting).collect()
+    }
+}
+
+// "10 KiB" -> ("10", "KiB")
+fn split_unit(raw: &str) -> (&str, &str) {
+    let raw = raw.trim();
+    let at = raw
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(raw.len());
+    (&raw[..at], raw[at..].trim())
+}
+
+// An empty value is an empty list
+pub(crate) fn list_items(raw: &str) -> impl Iterator<Item = &str> {
+    let raw = raw.trim();
+    raw.split(',')
+        .map(str::trim)
+        .filter(move |_| !raw.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(Duration::from_setting("30"), Some(Duration::from_secs(30)));
+        assert_eq!(
+            Duration::from_setting("150ms"),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(
+            Duration::from_setting("1.5 h"),
+            Some(Duration::from_mins(90))
+        );
+        assert_eq!(Duration::from_setting("5 weeks"), None);
+        assert_eq!(Duration::from_setting("-1s"), None);
+    }
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(ByteSize::from_setting("512"), Some(ByteSize::new(512)));
+        assert_eq!(ByteSize::from_setting("10KB"), Some(ByteSize::new(10_000)));
+        assert_eq!(
+            ByteSize::from_setting("4 MiB"),
+            Some(ByteSize::new(4 << 20))
+        );
+        assert_eq!(ByteSize::from_setting("99999999 TiB"), None);
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(Vec::<u16>::from_setting(" 80, 443 "), Some(vec![80, 443]));
+        assert_eq!(Vec::<u16>::from_setting(""), Some(vec![]));
+        assert_eq!(Vec::<u16>::from_setting("80,,443"), None);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let ports = SettingType::List(Box::new(SettingType::UInt));
+        assert_eq!(
+            Value::parse(&ports, "1,2"),
+            Some(Value::List(vec![Value::UInt(1), Value::UInt(2)]))
+        );
+        assert_eq!(
+            Value::parse(&SettingType::Bool, "Off"),
+            Some(Value::Bool(false))
+        );
+        assert_eq!(Value::parse(&SettingType::UInt, "-1"), None);
+        assert_eq!(Value::parse(&SettingType::Float, "NaN"), None);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{custom_error::ConfigError, reader::ConfigReader};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, mem,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, RwLock, mpsc},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How a setting changed between two loads of a config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Changed { old: String, new: String },
+}
+
+/// The settings that changed between two loads, sorted by key
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    changes: BTreeMap<String, Change>,
+}
+
+impl Diff {
+    #[must_use]
+    pub fn between(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Self {
+        let mut changes = BTreeMap::new();
+        for (key, old_value) in old {
+            match new.get(key) {
+                None => {
+                    changes.insert(key.clone(), Change::Removed(old_value.clone()));
+                }
+                Some(new_value) if new_value != old_value => {
+                    changes.insert(
+                        key.clone(),
+                        Change::Changed {
+                            old: old_value.clone(),
+                            new: new_value.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, new_value) in new {
+            if !old.contains_key(key) {
+                changes.insert(key.clone(), Change::Added(new_value.clone()));
+            }
+        }
+        Self { changes }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Change> {
+        self.changes.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Change)> {
+        self.changes
+            .iter()
+            .map(|(key, change)| (key.as_str(), change))
+    }
+}
+
+type Subscriber = Box<dyn Fn(&Diff, &ConfigReader) + Send + Sync>;
+// Modification time and length of every file, if it can be read
+type Stamp = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+type ErrorSubscriber = Box<dyn Fn(&ConfigError) + Send + Sync>;
+
+/// Reloads the config file of a `ConfigReader` when it changes, see `ConfigReader::watch`
+///
+/// A reload that fails to read, parse or validate is rejected and the last good config is kept.
+/// Clones share the same config and subscribers
+#[derive(Clone)]
+pub struct ConfigWatcher {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    // An unloaded reader with the config, format and schema to load with
+    template: ConfigReader,
+    current: RwLock<Arc<ConfigReader>>,
+    // The config file and its includes as of the last check, also serializes reloads
+    stamp: Mutex<Stamp>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    error_subscribers: Mutex<Vec<ErrorSubscriber>>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(template: ConfigReader) -> Result<Self, ConfigError> {
+        let mut reader = template.clone();
+        reader.load_config()?;
+        let stamp = Self::stamp(reader.files());
+
+        Ok(Self {
+            shared: Arc::new(Shared {
+                template,
+                current: RwLock::new(Arc::new(reader)),
+                stamp: Mutex::new(stamp),
+                subscribers: Mutex::new(Vec::new()),
+                error_subscribers: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    /// The last good config, it is not affected by later reloads
+    #[must_use]
+    pub fn current(&self) -> Arc<ConfigReader> {
+        Arc::clone(
+            &self
+                .shared
+                .current
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Called with the diff and the new config after every reload that changed a setting
+    ///
+    /// Subscribers must not subscribe or reload themselves, that would deadlock
+    pub fn subscribe(&self, subscriber: impl Fn(&Diff, &ConfigReader) + Send + Sync + 'static) {
+        lock(&self.shared.subscribers).push(Box::new(subscriber));
+    }
+
+    /// Called with the error of every rejected reload
+    pub fn on_error(&self, subscriber: impl Fn(&ConfigError) + Send + Sync + 'static) {
+        lock(&self.shared.error_subscribers).push(Box::new(subscriber));
+    }
+
+    /// Loads the file again and swaps it in if it is valid
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `ConfigReader::load_config`, the current config is kept then
+    pub fn reload(&self) -> Result<Diff, ConfigError> {
+        let mut stamp = lock(&self.shared.stamp);
+        *stamp = Self::restamp(&stamp);
+        self.load(&mut stamp)
+    }
+
+    /// Reloads if the modification time or the length of the file or a file it includes
+    /// changed since the last check
+    ///
+    /// # Errors
+    ///
+    /// See `reload`
+    pub fn check(&self) -> Result<Option<Diff>, ConfigError> {
+        let mut stamp = lock(&self.shared.stamp);
+        let new_stamp = Self::restamp(&stamp);
+        if new_stamp == *stamp {
+            return Ok(None);
+        }
+        // A broken file is reported once, not on every check
+        *stamp = new_stamp;
+        self.load(&mut stamp).map(Some)
+    }
+
+    /// Checks the file every `interval` on a thread until the handle is stopped or dropped
+    #[must_use]
+    pub fn spawn(&self, interval: Duration) -> WatchHandle {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let watcher = self.clone();
+        let thread = thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                // Errors go to the error subscribers
+                let _ = watcher.check();
+            }
+        });
+
+        WatchHandle { stop, thread }
+    }
+
+    // Callers hold the stamp lock, so reloads don't interleave
+    fn load(&self, stamp: &mut Stamp) -> Result<Diff, ConfigError> {
+        let mut reader = self.shared.template.clone();
+        if let Err(e) = reader.load_config() {
+            for subscriber in lock(&self.shared.error_subscribers).iter() {
+                subscriber(&e);
+            }
+            return Err(e);
+        }
+
+        let new = Arc::new(reader);
+        let old = mem::replace(
+            &mut *self
+                .shared
+                .current
+                .write()
+                .unwrap_or_else(PoisonError::into_inner),
+            Arc::clone(&new),
+        );
+        // Files included for the first time are stamped as they are now
+        *stamp = new
+            .files()
+            .iter()
+            .map(|path| match stamp.iter().find(|(seen, _)| seen == path) {
+                Some(seen) => seen.clone(),
+                None => (path.clone(), Self::file_stamp(path)),
+            })
+            .collect();
+        let diff = Diff::between(old.values(), new.values());
+        if !diff.is_empty() {
+            for subscriber in lock(&self.shared.subscribers).iter() {
+                subscriber(&diff, &new);
+            }
+        }
+        Ok(diff)
+    }
+
+    fn stamp(files: &[PathBuf]) -> Stamp {
+        files
+            .iter()
+            .map(|path| (path.clone(), Self::file_stamp(path)))
+            .collect()
+    }
+
+    // The same files as they are now
+    fn restamp(stamp: &Stamp) -> Stamp {
+        stamp
+            .iter()
+            .map(|(path, _)| (path.clone(), Self::file_stamp(path)))
+            .collect()
+    }
+
+    fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+}
+
+/// Stops the watching thread of `ConfigWatcher::spawn` when stopped or dropped
+pub struct WatchHandle {
+    stop: mpsc::Sender<()>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl WatchHandle {
+    /// Stops the thread and waits for it to finish
+    pub fn stop(self) {
+        // The thread stops either way, it has only ended early if it panicked
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+// A panicking subscriber must not stop the reloads
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::Config,
+        schema::{Schema, SettingSpec},
+        value::SettingType,
+    };
+
+    fn temp_config(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}_{}.ini", std::process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn watcher(path: &Path) -> ConfigWatcher {
+        let config = Config::new(path.to_str().unwrap()).unwrap();
+        let schema = Schema::new().setting(
+            "max_retries",
+            SettingSpec::new(SettingType::UInt).range("1", "10"),
+        );
+        ConfigReader::new(Some(&config))
+            .unwrap()
+            .with_schema(schema)
+            .watch()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = [("a", "1"), ("b", "2")].map(|(k, v)| (k.to_string(), v.to_string()));
+        let new = [("b", "3"), ("c", "4")].map(|(k, v)| (k.to_string(), v.to_string()));
+        let diff = Diff::between(&old.into(), &new.into());
+
+        assert_eq!(diff.get("a"), Some(&Change::Removed("1".to_string())));
+        assert_eq!(
+            diff.get("b"),
+            Some(&Change::Changed {
+                old: "2".to_string(),
+                new: "3".to_string()
+            })
+        );
+        assert_eq!(diff.get("c"), Some(&Change::Added("4".to_string())));
+        assert_eq!(diff.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_reload_notifies_and_rejects_invalid_configs() {
+        let path = temp_config("watch_reload", "max_retries = 3\nname = crab\n");
+        let watcher = watcher(&path);
+        let diffs = Arc::new(Mutex::new(Vec::new()));
+        let errors = Arc::new(Mutex::new(0));
+        {
+            let diffs = Arc::clone(&diffs);
+            watcher.subscribe(move |diff, _| diffs.lock().unwrap().push(diff.clone()));
+            let errors = Arc::clone(&errors);
+            watcher.on_error(move |_| *errors.lock().unwrap() += 1);
+        }
+
+        fs::write(&path, "max_retries = 5\nname = crab\n").unwrap();
+        let diff = watcher.reload().unwrap();
+        assert!(matches!(diff.get("max_retries"), Some(Change::Changed { new, .. }) if new == "5"));
+        assert_eq!(diffs.lock().unwrap().len(), 1);
+
+        fs::write(&path, "max_retries = 50\n").unwrap();
+        assert!(matches!(
+            watcher.reload(),
+            Err(ConfigError::OutOfRange { .. })
+        ));
+        assert_eq!(watcher.current().get::<u32>("max_retries").unwrap(), 5);
+        assert_eq!(*errors.lock().unwrap(), 1);
+
+        // Nothing changed, nobody is notified
+        fs::write(&path, "name = crab\nmax_retries = 5\n").unwrap();
+        assert!(watcher.reload().unwrap().is_empty());
+        assert_eq!(diffs.lock().unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_spawned_watcher_picks_up_changes() {
+        let path = temp_config("watch_spawn", "max_retries = 3\n");
+        let watcher = watcher(&path);
+        let (sender, changes) = mpsc::channel();
+        watcher.subscribe(move |diff, _| sender.send(diff.clone()).unwrap());
+        let handle = watcher.spawn(Duration::from_millis(10));
+
+        fs::write(&path, "max_retries = 4\nname = crab\n").unwrap();
+        let diff = changes.recv_timeout(Duration::from_secs(5)).unwrap();
+        handle.stop();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(diff.get("name"), Some(&Change::Added("crab".to_string())));
+        assert_eq!(watcher.current().get::<u32>("max_retries").unwrap(), 4);
+    }
+
+    #[test]
+    fn test_check_picks_up_included_files() {
+        let dir = std::env::temp_dir().join(format!("watch_include_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.ini");
+        fs::write(
+            dir.join("base.ini"),
+            "max_retries = 3
+",
+        )
+        .unwrap();
+        fs::write(
+            &path,
+            "include = base.ini
+name = crab
+",
+        )
+        .unwrap();
+        let watcher = watcher(&path);
+        assert!(watcher.check().unwrap().is_none());
+
+        fs::write(
+            dir.join("base.ini"),
+            "max_retries = 10
+",
+        )
+        .unwrap();
+        let diff = watcher.check().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            diff.unwrap().get("max_retries"),
+            Some(Change::Changed { new, .. }) if new == "10"
+        ));
+    }
+}